@@ -18,6 +18,9 @@ struct CliArgs {
     /// (which can be also located in `.env` file).
     #[arg(short = 'k', long, value_name = "FILE")]
     service_account_key_path: Option<PathBuf>,
+    /// Print the JSON request body before sending.
+    #[arg(long)]
+    print_payload: bool,
 }
 
 #[tokio::main]
@@ -47,6 +50,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         webpush: None,
     };
 
+    if args.print_payload {
+        println!("Payload: {}", message.to_pretty_json());
+    }
+
     let response = client.send(message).await?;
     println!("Response: {:#?}", response);
 
@@ -1,4 +1,6 @@
-use serde::Serialize;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::{
@@ -6,12 +8,12 @@ use super::{
     android_notification::AndroidNotification,
 };
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#androidconfig>
 pub struct AndroidConfig {
     /// An identifier of a group of messages that can be collapsed, so that only the last message gets
     /// sent when delivery can be resumed.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "collapseKey")]
     pub collapse_key: Option<String>,
 
     /// Message priority.
@@ -24,7 +26,7 @@ pub struct AndroidConfig {
     pub ttl: Option<String>,
 
     /// Package name of the application where the registration token must match in order to receive the message.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "restrictedPackageName")]
     pub restricted_package_name: Option<String>,
 
     /// Arbitrary key/value payload.
@@ -36,10 +38,123 @@ pub struct AndroidConfig {
     pub notification: Option<AndroidNotification>,
 
     /// Options for features provided by the FCM SDK for Android.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "fcmOptions")]
     pub fcm_options: Option<AndroidFcmOptions>,
 
     /// If set to true, messages will be allowed to be delivered to the app while the device is in direct boot mode.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "directBootOk")]
     pub direct_boot_ok: Option<bool>,
 }
+
+impl AndroidConfig {
+    /// Config for a data message which is delivered also while the
+    /// device is in direct boot mode: `data`, `direct_boot_ok: true` and
+    /// high priority, without a notification.
+    pub fn direct_boot_data_message(data: Value) -> AndroidConfig {
+        AndroidConfig {
+            data: Some(data),
+            priority: Some(AndroidMessagePriority::High),
+            direct_boot_ok: Some(true),
+            ..Default::default()
+        }
+    }
+
+    /// Set `collapse_key`.
+    pub fn with_collapse_key(mut self, collapse_key: impl Into<String>) -> Self {
+        self.collapse_key = Some(collapse_key.into());
+        self
+    }
+
+    /// Set `priority`.
+    pub fn with_priority(mut self, priority: AndroidMessagePriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Set `ttl` from a [Duration], see [AndroidConfig::set_ttl_duration].
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.set_ttl_duration(ttl);
+        self
+    }
+
+    /// Set `restricted_package_name`.
+    pub fn with_restricted_package_name(mut self, restricted_package_name: impl Into<String>) -> Self {
+        self.restricted_package_name = Some(restricted_package_name.into());
+        self
+    }
+
+    /// Set `data`.
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Set `notification`.
+    pub fn with_notification(mut self, notification: AndroidNotification) -> Self {
+        self.notification = Some(notification);
+        self
+    }
+
+    /// Set `fcm_options`.
+    pub fn with_fcm_options(mut self, fcm_options: AndroidFcmOptions) -> Self {
+        self.fcm_options = Some(fcm_options);
+        self
+    }
+
+    /// Set `direct_boot_ok`. Only data is delivered in direct boot mode,
+    /// see [crate::message::Lint::DirectBootOkWithNotification].
+    pub fn direct_boot_ok(mut self, direct_boot_ok: bool) -> Self {
+        self.direct_boot_ok = Some(direct_boot_ok);
+        self
+    }
+
+    /// Set `ttl` from a [Duration]. The value is written in the protobuf
+    /// Duration format, e.g. `"3.5s"`.
+    pub fn set_ttl_duration(&mut self, ttl: Duration) {
+        self.ttl = Some(format_duration(ttl));
+    }
+
+    /// Parse `ttl` to a [Duration]. Returns `None` if `ttl` is not set.
+    pub fn ttl_duration(&self) -> Option<Result<Duration, TtlParseError>> {
+        self.ttl.as_deref().map(parse_duration)
+    }
+}
+
+/// Error of [AndroidConfig::ttl_duration].
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("TTL is not seconds with up to nine fractional digits and an `s` suffix: {0:?}")]
+pub struct TtlParseError(pub String);
+
+/// Parse the format of [format_duration].
+fn parse_duration(value: &str) -> Result<Duration, TtlParseError> {
+    let error = || TtlParseError(value.to_string());
+    let seconds = value.strip_suffix('s').ok_or_else(error)?;
+    let (whole, fraction) = match seconds.split_once('.') {
+        Some((_, "")) => return Err(error()),
+        Some((whole, fraction)) => (whole, fraction),
+        None => (seconds, ""),
+    };
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || fraction.len() > 9 || !is_digits(fraction) {
+        return Err(error());
+    }
+    let secs = whole.parse().map_err(|_| error())?;
+    let nanos = if fraction.is_empty() {
+        0
+    } else {
+        format!("{:0<9}", fraction).parse().map_err(|_| error())?
+    };
+    Ok(Duration::new(secs, nanos))
+}
+
+/// Format a [Duration] as seconds with up to nine fractional digits and
+/// an `s` suffix.
+fn format_duration(duration: Duration) -> String {
+    let nanos = duration.subsec_nanos();
+    if nanos == 0 {
+        format!("{}s", duration.as_secs())
+    } else {
+        let fraction = format!("{:09}", nanos);
+        format!("{}.{}s", duration.as_secs(), fraction.trim_end_matches('0'))
+    }
+}
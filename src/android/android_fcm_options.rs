@@ -1,8 +1,14 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Serialize)]
-/// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#androidconfig>
+use crate::message::AnalyticsLabel;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#androidfcmoptions>
+///
+/// Android image is [crate::message::AndroidNotification::image], as the
+/// FCM API does not have an image option here.
 pub struct AndroidFcmOptions {
     /// Label associated with the message's analytics data.
-    pub analytics_label: String,
+    #[serde(alias = "analyticsLabel")]
+    pub analytics_label: AnalyticsLabel,
 }
@@ -1,9 +1,28 @@
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#androidmessagepriority>
+///
+/// Deserializing ignores case, so that for example `"high"`, `"High"`
+/// and `"HIGH"` are all accepted.
 pub enum AndroidMessagePriority {
     Normal,
     High,
 }
+
+impl<'de> Deserialize<'de> for AndroidMessagePriority {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let priority = String::deserialize(deserializer)?;
+        if priority.eq_ignore_ascii_case("NORMAL") {
+            Ok(AndroidMessagePriority::Normal)
+        } else if priority.eq_ignore_ascii_case("HIGH") {
+            Ok(AndroidMessagePriority::High)
+        } else {
+            Err(serde::de::Error::unknown_variant(&priority, &["NORMAL", "HIGH"]))
+        }
+    }
+}
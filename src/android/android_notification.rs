@@ -1,8 +1,10 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::notification::Notification;
 
 use super::{light_settings::LightSettings, notification_priority::NotificationPriority, visibility::Visibility};
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#androidnotification>
 pub struct AndroidNotification {
     /// The notification's title.
@@ -30,31 +32,31 @@ pub struct AndroidNotification {
     pub tag: Option<String>,
 
     /// The action associated with a user click on the notification.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "clickAction")]
     pub click_action: Option<String>,
 
     /// The key to the body string in the app's string resources to use to localize the body text to the user's
     /// current localization.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "bodyLocKey")]
     pub body_loc_key: Option<String>,
 
     /// Variable string values to be used in place of the format specifiers in body_loc_key to use to localize the
     /// body text to the user's current localization.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "bodyLocArgs")]
     pub body_loc_args: Option<Vec<String>>,
 
     /// The key to the title string in the app's string resources to use to localize the title text to the user's
     /// current localization.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "titleLocKey")]
     pub title_loc_key: Option<String>,
 
     /// Variable string values to be used in place of the format specifiers in title_loc_key to use to localize the
     /// title text to the user's current localization.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "titleLocArgs")]
     pub title_loc_args: Option<Vec<String>>,
 
     /// The notification's channel id (new in Android O).
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "channelId")]
     pub channel_id: Option<String>,
 
     /// Sets the "ticker" text, which is sent to accessibility services.
@@ -67,32 +69,32 @@ pub struct AndroidNotification {
 
     /// Set the time that the event in the notification occurred. Notifications in the panel are sorted by this time.
     /// Timestamp format: <https://developers.google.com/protocol-buffers/docs/reference/google.protobuf?authuser=0#google.protobuf.Timestamp>
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "eventTime")]
     pub event_time: Option<String>,
 
     /// Set whether or not this notification is relevant only to the current device.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "localOnly")]
     pub local_only: Option<bool>,
 
     /// Set the relative priority for this notification.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "notificationPriority")]
     pub notification_priority: Option<NotificationPriority>,
 
     /// If set to true, use the Android framework's default sound for the notification.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "defaultSound")]
     pub default_sound: Option<bool>,
 
     /// If set to true, use the Android framework's default vibrate pattern for the notification.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "defaultVibrateTimings")]
     pub default_vibrate_timings: Option<bool>,
 
     /// If set to true, use the Android framework's default LED light settings for the notification.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "defaultLightSettings")]
     pub default_light_settings: Option<bool>,
 
     /// Set the vibration pattern to use
     /// Duration format: <https://developers.google.com/protocol-buffers/docs/reference/google.protobuf?authuser=0#google.protobuf.Duration>
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "vibrateTimings")]
     pub vibrate_timings: Option<Vec<String>>,
 
     /// Set the Notification.visibility of the notification.
@@ -100,14 +102,43 @@ pub struct AndroidNotification {
     pub visibility: Option<Visibility>,
 
     /// Sets the number of items this notification represents.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "notificationCount")]
     pub notification_count: Option<i32>,
 
     /// Settings to control the notification's LED blinking rate and color if LED is available on the device.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "lightSettings")]
     pub light_settings: Option<LightSettings>,
 
     /// Contains the URL of an image that is going to be displayed in a notification.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image: Option<String>,
 }
+
+/// Error cases of [AndroidNotification::validate].
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum AndroidNotificationError {
+    #[error("Notification count must not be negative: {0}")]
+    NegativeNotificationCount(i32),
+}
+
+impl AndroidNotification {
+    /// Check that `notification_count` is not negative.
+    pub fn validate(&self) -> Result<(), AndroidNotificationError> {
+        match self.notification_count {
+            Some(count) if count < 0 => Err(AndroidNotificationError::NegativeNotificationCount(count)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<&Notification> for AndroidNotification {
+    /// Copy `title`, `body` and `image`.
+    fn from(notification: &Notification) -> Self {
+        AndroidNotification {
+            title: notification.title.clone(),
+            body: notification.body.clone(),
+            image: notification.image.clone(),
+            ..Default::default()
+        }
+    }
+}
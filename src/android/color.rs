@@ -1,6 +1,6 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#Color>
 pub struct Color {
     /// The amount of red in the color as a value in the interval [0, 1].
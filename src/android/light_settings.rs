@@ -1,8 +1,8 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use super::color::Color;
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#LightSettings>
 pub struct LightSettings {
     /// Set color of the LED with google.type.Color.
@@ -10,9 +10,11 @@ pub struct LightSettings {
 
     /// Along with light_off_duration, define the blink rate of LED flashes
     /// Duration format: <https://developers.google.com/protocol-buffers/docs/reference/google.protobuf?authuser=0#google.protobuf.Duration>
+    #[serde(alias = "lightOnDuration")]
     pub light_on_duration: String,
 
     /// Along with light_on_duration, define the blink rate of LED flashes.
     /// Duration format: <https://developers.google.com/protocol-buffers/docs/reference/google.protobuf?authuser=0#google.protobuf.Duration>
+    #[serde(alias = "lightOffDuration")]
     pub light_off_duration: String,
 }
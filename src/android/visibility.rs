@@ -1,6 +1,6 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#visibility>
 pub enum Visibility {
@@ -1,9 +1,9 @@
-use serde::Serialize;
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use super::apns_fcm_options::ApnsFcmOptions;
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#apnsconfig>
 pub struct ApnsConfig {
     /// HTTP request headers defined in Apple Push Notification Service.
@@ -15,6 +15,121 @@ pub struct ApnsConfig {
     pub payload: Option<Value>,
 
     /// Options for features provided by the FCM SDK for iOS.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "fcmOptions")]
     pub fcm_options: Option<ApnsFcmOptions>,
 }
+
+/// APNs request header for the notification UUID.
+pub const APNS_ID_HEADER: &str = "apns-id";
+
+/// APNs request header for the notification type.
+pub const APNS_PUSH_TYPE_HEADER: &str = "apns-push-type";
+
+/// Error cases of [ApnsConfig::validate].
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ApnsError {
+    #[error("Payload has both `content-available: 1` and `alert`")]
+    SilentWithAlert,
+}
+
+impl ApnsConfig {
+    /// Check that the `aps` dictionary of the payload is not both a
+    /// background (`content-available: 1`) and an alert notification.
+    /// iOS behavior is undefined for that combination and the
+    /// background wake is often dropped.
+    pub fn validate(&self) -> Result<(), ApnsError> {
+        let aps = match self.payload.as_ref().and_then(|payload| payload.get("aps")) {
+            Some(aps) => aps,
+            None => return Ok(()),
+        };
+        let is_silent = aps.get("content-available").and_then(Value::as_i64) == Some(1);
+        if is_silent && aps.get("alert").is_some() {
+            return Err(ApnsError::SilentWithAlert);
+        }
+        Ok(())
+    }
+
+    /// Set `headers`, replacing all previously set headers.
+    pub fn with_headers(mut self, headers: Value) -> Self {
+        self.headers = Some(headers);
+        self
+    }
+
+    /// Set APNs request header `name`, see [ApnsConfig::set_header].
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_header(name, value);
+        self
+    }
+
+    /// Set `payload`.
+    pub fn with_payload(mut self, payload: Value) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    /// Set `fcm_options`.
+    pub fn with_fcm_options(mut self, fcm_options: ApnsFcmOptions) -> Self {
+        self.fcm_options = Some(fcm_options);
+        self
+    }
+
+    /// Set `apns-id` header. APNs requires the value to be a UUID in the
+    /// `8-4-4-4-12` hexadecimal form. APNs uses the same value in its
+    /// responses to the sender (FCM), but FCM does not forward it.
+    pub fn apns_id(mut self, apns_id: impl Into<String>) -> Self {
+        self.set_header(APNS_ID_HEADER, apns_id.into());
+        self
+    }
+
+    /// Set `apns-id` header to a random UUID if it is not already set.
+    #[cfg(feature = "uuid")]
+    pub fn ensure_apns_id(&mut self) {
+        if self.header(APNS_ID_HEADER).is_none() {
+            self.set_header(APNS_ID_HEADER, uuid::Uuid::new_v4().hyphenated().to_string());
+        }
+    }
+
+    /// Set `apns-push-type` header from the `aps` dictionary of the
+    /// payload if the header is not already set:
+    ///
+    /// | `aps` has                          | `apns-push-type` |
+    /// |------------------------------------|------------------|
+    /// | `alert`, `sound` or `badge`        | `alert`          |
+    /// | only `content-available: 1`        | `background`     |
+    ///
+    /// Otherwise the header is not set.
+    pub fn auto_push_type(&mut self) {
+        if self.header(APNS_PUSH_TYPE_HEADER).is_some() {
+            return;
+        }
+        let aps = match self.payload.as_ref().and_then(|payload| payload.get("aps")) {
+            Some(aps) => aps,
+            None => return,
+        };
+        let push_type = if ["alert", "sound", "badge"].iter().any(|key| aps.get(key).is_some()) {
+            "alert"
+        } else if aps.get("content-available").and_then(Value::as_i64) == Some(1) {
+            "background"
+        } else {
+            return;
+        };
+        self.set_header(APNS_PUSH_TYPE_HEADER, push_type);
+    }
+
+    /// Get string value of APNs request header `name`.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.as_ref()?.get(name)?.as_str()
+    }
+
+    /// Set APNs request header `name`. If `headers` is not a JSON object
+    /// it is replaced with an object.
+    pub fn set_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let headers = self.headers.get_or_insert_with(|| Value::Object(Map::new()));
+        if !headers.is_object() {
+            *headers = Value::Object(Map::new());
+        }
+        if let Value::Object(headers) = headers {
+            headers.insert(name.into(), Value::String(value.into()));
+        }
+    }
+}
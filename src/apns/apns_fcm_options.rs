@@ -1,11 +1,15 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Serialize)]
+use crate::message::AnalyticsLabel;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#apnsfcmoptions>
 pub struct ApnsFcmOptions {
     /// Label associated with the message's analytics data.
-    pub analytics_label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "analyticsLabel")]
+    pub analytics_label: Option<AnalyticsLabel>,
 
     /// Contains the URL of an image that is going to be displayed in a notification.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub image: Option<String>,
 }
@@ -0,0 +1,80 @@
+use std::future::Future;
+use std::sync::OnceLock;
+
+use tokio::runtime::{Handle, Runtime};
+
+use super::{response::FcmResponse, FcmClient, FcmClientBuilder, FcmClientError};
+use crate::message::Message;
+
+/// Current-thread runtime for running futures to completion from
+/// synchronous code. The runtime is created on first use and kept, so
+/// that connections and background tasks of the HTTP and OAuth clients
+/// stay alive between blocking calls.
+#[derive(Default)]
+pub(crate) struct BlockingRuntime(OnceLock<Runtime>);
+
+impl BlockingRuntime {
+    #[cfg(test)]
+    pub(crate) fn is_created(&self) -> bool {
+        self.0.get().is_some()
+    }
+
+    /// Blocking an async runtime thread would panic or deadlock, so an
+    /// error is returned in an async context.
+    fn block_on<F: Future>(&self, future: F) -> Result<F::Output, FcmClientError> {
+        if Handle::try_current().is_ok() {
+            return Err(FcmClientError::BlockingInAsyncContext);
+        }
+        let runtime = match self.0.get() {
+            Some(runtime) => runtime,
+            None => {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .map_err(FcmClientError::RuntimeCreatingFailed)?;
+                self.0.get_or_init(|| runtime)
+            }
+        };
+        Ok(runtime.block_on(future))
+    }
+}
+
+impl Drop for BlockingRuntime {
+    /// Dropping a runtime in an async context panics, so the runtime is
+    /// shut down without waiting for its tasks.
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl FcmClientBuilder {
+    /// Same as [FcmClientBuilder::build] but blocks the current thread
+    /// on a current-thread runtime, for code which is not async, for
+    /// example CLI tools. Use [FcmClient::send_blocking] to send messages
+    /// without an async runtime. The client keeps the runtime, so that
+    /// sending runs on the same runtime as the client was built on.
+    ///
+    /// Returns [FcmClientError::BlockingInAsyncContext] if called from
+    /// an async context.
+    pub fn build_blocking(self) -> Result<FcmClient, FcmClientError> {
+        let runtime = BlockingRuntime::default();
+        let mut client = runtime.block_on(self.build())??;
+        client.blocking_runtime = runtime;
+        Ok(client)
+    }
+}
+
+impl FcmClient {
+    /// Same as [FcmClient::send] but blocks the current thread until the
+    /// message is sent. The current-thread runtime which is used for
+    /// sending is created on the first call and reused by later calls,
+    /// so that connections to FCM are reused.
+    ///
+    /// Returns [FcmClientError::BlockingInAsyncContext] if called from
+    /// an async context.
+    pub fn send_blocking(&self, message: &Message) -> Result<FcmResponse, FcmClientError> {
+        self.blocking_runtime.block_on(self.send(message))?
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use super::Clock;
+
+/// Configuration for the circuit breaker of [crate::FcmClient].
+///
+/// After `failure_threshold` consecutive retryable failures (see
+/// [crate::response::FcmResponseError::is_retryable] and HTTP request
+/// errors) sending fails with [crate::FcmClientError::CircuitOpen]
+/// without contacting FCM until `cool_down` has elapsed. After that one
+/// message is sent as a trial. If the trial succeeds the circuit is
+/// closed, otherwise it is opened again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircuitBreakerConfig {
+    pub failure_threshold: u32,
+    pub cool_down: Duration,
+}
+
+#[derive(Debug)]
+enum CircuitState {
+    Closed { consecutive_failures: u32 },
+    Open { until: DateTime<Utc> },
+    HalfOpen { trial_started: DateTime<Utc> },
+}
+
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<CircuitState>,
+    clock: Arc<dyn Clock>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            config,
+            state: Mutex::new(CircuitState::Closed {
+                consecutive_failures: 0,
+            }),
+            clock,
+        }
+    }
+
+    /// Returns `false` if the request should not be sent.
+    pub fn allow_request(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let now = self.clock.now();
+        match *state {
+            CircuitState::Closed { .. } => true,
+            CircuitState::Open { until } if now < until => false,
+            // Allow a new trial also if the previous trial request
+            // did not complete (for example the future was dropped).
+            CircuitState::HalfOpen { trial_started } if now < self.cool_down_end(trial_started) => false,
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => {
+                *state = CircuitState::HalfOpen { trial_started: now };
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = CircuitState::Closed {
+            consecutive_failures: 0,
+        };
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        let consecutive_failures = match *state {
+            CircuitState::Closed { consecutive_failures } => consecutive_failures + 1,
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => self.config.failure_threshold,
+        };
+        *state = if consecutive_failures >= self.config.failure_threshold {
+            CircuitState::Open {
+                until: self.cool_down_end(self.clock.now()),
+            }
+        } else {
+            CircuitState::Closed { consecutive_failures }
+        };
+    }
+
+    /// End of the cool-down which starts at `start`. Cool-down too long
+    /// to be represented never ends.
+    fn cool_down_end(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        chrono::Duration::from_std(self.config.cool_down)
+            .ok()
+            .and_then(|cool_down| start.checked_add_signed(cool_down))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
@@ -0,0 +1,20 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+/// Source of the current time for [crate::FcmClient], for example for
+/// `Retry-After` wait times and access token expiration. Tests can use
+/// a fake clock instead of sleeping.
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// [Clock] which returns the operating system time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
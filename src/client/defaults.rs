@@ -0,0 +1,239 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::message::{AndroidConfig, ApnsConfig, Message};
+
+/// Values which [crate::FcmClient] fills in to messages which do not
+/// set them.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MessageDefaults {
+    /// Used as a whole for messages which do not have `android`.
+    pub android: Option<AndroidConfig>,
+    /// Used as a whole for messages which do not have `apns`.
+    pub apns: Option<ApnsConfig>,
+    pub android_ttl: Option<Duration>,
+    pub apns_headers: HashMap<String, String>,
+    #[cfg(feature = "uuid")]
+    pub generate_apns_id: bool,
+}
+
+impl MessageDefaults {
+    /// Returns the message unchanged (borrowed) when no default applies.
+    pub fn apply<'a>(&self, mut message: Cow<'a, Message>) -> Cow<'a, Message> {
+        if let Some(android) = &self.android {
+            if message.android.is_none() {
+                message.to_mut().android = Some(android.clone());
+            }
+        }
+
+        if let Some(apns) = &self.apns {
+            if message.apns.is_none() {
+                message.to_mut().apns = Some(apns.clone());
+            }
+        }
+
+        if let Some(ttl) = self.android_ttl {
+            let has_ttl = message.android.as_ref().is_some_and(|android| android.ttl.is_some());
+            if !has_ttl {
+                message
+                    .to_mut()
+                    .android
+                    .get_or_insert_with(AndroidConfig::default)
+                    .set_ttl_duration(ttl);
+            }
+        }
+
+        for (name, value) in &self.apns_headers {
+            let has_header = message.apns.as_ref().is_some_and(|apns| apns.header(name).is_some());
+            if !has_header {
+                message
+                    .to_mut()
+                    .apns
+                    .get_or_insert_with(ApnsConfig::default)
+                    .set_header(name.as_str(), value.as_str());
+            }
+        }
+
+        #[cfg(feature = "uuid")]
+        if self.generate_apns_id {
+            let has_apns_id = message
+                .apns
+                .as_ref()
+                .is_some_and(|apns| apns.header(crate::message::APNS_ID_HEADER).is_some());
+            if !has_apns_id {
+                message
+                    .to_mut()
+                    .apns
+                    .get_or_insert_with(ApnsConfig::default)
+                    .ensure_apns_id();
+            }
+        }
+
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Target;
+    use serde_json::json;
+
+    fn message(android: Option<AndroidConfig>) -> Message {
+        Message {
+            data: None,
+            notification: None,
+            target: Target::Token("token".to_string()),
+            android,
+            webpush: None,
+            apns: None,
+            fcm_options: None,
+        }
+    }
+
+    #[test]
+    fn should_add_default_android_ttl_to_message_without_android_config() {
+        let defaults = MessageDefaults {
+            android_ttl: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        let message = message(None);
+
+        let payload = serde_json::to_value(defaults.apply(Cow::Borrowed(&message)).as_ref()).unwrap();
+
+        let expected_payload = json!({
+            "android": {
+                "ttl": "3600s",
+            },
+            "token": "token",
+        });
+
+        assert_eq!(expected_payload, payload);
+    }
+
+    #[test]
+    fn should_keep_message_android_ttl() {
+        let defaults = MessageDefaults {
+            android_ttl: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        let message = message(Some(AndroidConfig {
+            ttl: Some("10s".to_string()),
+            ..Default::default()
+        }));
+
+        let applied = defaults.apply(Cow::Borrowed(&message));
+
+        assert!(matches!(applied, Cow::Borrowed(_)));
+        assert_eq!(Some("10s"), applied.android.as_ref().unwrap().ttl.as_deref());
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn should_generate_apns_id() {
+        let defaults = MessageDefaults {
+            generate_apns_id: true,
+            ..Default::default()
+        };
+        let message = message(None);
+
+        let applied = defaults.apply(Cow::Borrowed(&message));
+        let apns_id = applied.apns.as_ref().unwrap().header("apns-id").unwrap();
+
+        let groups: Vec<&str> = apns_id.split('-').collect();
+        let group_lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+        assert_eq!(vec![8, 4, 4, 4, 12], group_lengths);
+        assert!(groups
+            .iter()
+            .all(|group| group.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())));
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn should_keep_message_apns_id() {
+        let defaults = MessageDefaults {
+            generate_apns_id: true,
+            ..Default::default()
+        };
+        let apns_id = "123e4567-e89b-12d3-a456-426614174000";
+        let mut message = message(None);
+        message.apns = Some(ApnsConfig::default().apns_id(apns_id));
+
+        let applied = defaults.apply(Cow::Borrowed(&message));
+
+        assert!(matches!(applied, Cow::Borrowed(_)));
+        let payload = serde_json::to_value(applied.as_ref()).unwrap();
+        assert_eq!(json!({ "apns-id": apns_id }), payload["apns"]["headers"]);
+    }
+
+    #[test]
+    fn should_format_fractional_android_ttl() {
+        let defaults = MessageDefaults {
+            android_ttl: Some(Duration::from_millis(3500)),
+            ..Default::default()
+        };
+        let message = message(Some(AndroidConfig {
+            collapse_key: Some("key".to_string()),
+            ..Default::default()
+        }));
+
+        let applied = defaults.apply(Cow::Borrowed(&message));
+        let android = applied.android.as_ref().unwrap();
+
+        assert_eq!(Some("3.5s"), android.ttl.as_deref());
+        assert_eq!(Some("key"), android.collapse_key.as_deref());
+    }
+
+    #[test]
+    fn should_add_default_apns_headers_without_overriding_message_headers() {
+        let defaults = MessageDefaults {
+            apns_headers: HashMap::from([
+                ("apns-priority".to_string(), "10".to_string()),
+                ("apns-push-type".to_string(), "alert".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let mut with_headers = message(None);
+        let mut apns = ApnsConfig::default();
+        apns.set_header("apns-priority", "5");
+        with_headers.apns = Some(apns);
+
+        let without_headers = serde_json::to_value(defaults.apply(Cow::Owned(message(None))).as_ref()).unwrap();
+        let with_headers = serde_json::to_value(defaults.apply(Cow::Owned(with_headers)).as_ref()).unwrap();
+
+        assert_eq!(
+            json!({ "apns-priority": "10", "apns-push-type": "alert" }),
+            without_headers["apns"]["headers"]
+        );
+        assert_eq!(
+            json!({ "apns-priority": "5", "apns-push-type": "alert" }),
+            with_headers["apns"]["headers"]
+        );
+    }
+
+    #[test]
+    fn should_use_default_platform_configs_only_for_messages_without_them() {
+        let defaults = MessageDefaults {
+            android: Some(
+                AndroidConfig::default()
+                    .with_collapse_key("default")
+                    .with_ttl(Duration::from_secs(60)),
+            ),
+            apns: Some(ApnsConfig::default().with_header("apns-priority", "5")),
+            ..Default::default()
+        };
+        let with_android = message(Some(AndroidConfig::default().with_collapse_key("own")));
+
+        let without_configs = serde_json::to_value(defaults.apply(Cow::Owned(message(None))).as_ref()).unwrap();
+        let with_android = serde_json::to_value(defaults.apply(Cow::Owned(with_android)).as_ref()).unwrap();
+
+        assert_eq!(
+            json!({ "collapse_key": "default", "ttl": "60s" }),
+            without_configs["android"]
+        );
+        assert_eq!(json!({ "headers": { "apns-priority": "5" } }), without_configs["apns"]);
+        assert_eq!(json!({ "collapse_key": "own" }), with_android["android"]);
+        assert_eq!(json!({ "headers": { "apns-priority": "5" } }), with_android["apns"]);
+    }
+}
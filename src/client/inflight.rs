@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokio::sync::Notify;
+
+/// Counter of in-flight requests.
+#[derive(Debug, Default)]
+pub(crate) struct InflightTracker {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+impl InflightTracker {
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Count the request as in-flight until the returned guard is
+    /// dropped.
+    pub fn start(&self) -> InflightGuard<'_> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InflightGuard { tracker: self }
+    }
+
+    pub async fn wait_idle(&self) {
+        loop {
+            // Create the future before checking the count, so that
+            // notification between the check and await is not missed.
+            let idle = self.idle.notified();
+            if self.count() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+pub(crate) struct InflightGuard<'a> {
+    tracker: &'a InflightTracker,
+}
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        if self.tracker.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.tracker.idle.notify_waiters();
+        }
+    }
+}
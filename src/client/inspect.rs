@@ -0,0 +1,39 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// Function which is called with every FCM request just before it is
+/// sent.
+pub type RequestInspector = Arc<dyn Fn(&reqwest::Request) + Send + Sync>;
+
+/// Function which is called with every FCM response before the
+/// response body is read.
+pub type ResponseInspector = Arc<dyn Fn(&reqwest::Response) + Send + Sync>;
+
+#[derive(Default, Clone)]
+pub(crate) struct Inspectors {
+    pub request: Option<RequestInspector>,
+    pub response: Option<ResponseInspector>,
+}
+
+impl Inspectors {
+    pub fn inspect_request(&self, request: &reqwest::Request) {
+        if let Some(inspector) = &self.request {
+            inspector(request);
+        }
+    }
+
+    pub fn inspect_response(&self, response: &reqwest::Response) {
+        if let Some(inspector) = &self.response {
+            inspector(response);
+        }
+    }
+}
+
+impl fmt::Debug for Inspectors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Inspectors")
+            .field("request", &self.request.is_some())
+            .field("response", &self.response.is_some())
+            .finish()
+    }
+}
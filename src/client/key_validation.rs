@@ -0,0 +1,80 @@
+use serde_json::Value;
+
+/// Fields which must be present in a service account key JSON.
+const REQUIRED_FIELDS: [&str; 4] = ["project_id", "private_key", "client_email", "token_uri"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum KeyValidationError {
+    #[error("Service account key is not valid JSON: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("Service account key is not a JSON object")]
+    NotObject,
+    #[error("Service account key field is missing or empty: {0}")]
+    MissingField(&'static str),
+}
+
+/// Check that `json` is a service account key with all fields required
+/// for sending messages. Useful for detecting truncated keys before
+/// building [crate::FcmClient], as those cause confusing OAuth errors.
+pub fn validate_service_account_key_json(json: &str) -> Result<(), KeyValidationError> {
+    let key: Value = serde_json::from_str(json)?;
+    let key = key.as_object().ok_or(KeyValidationError::NotObject)?;
+    for field in REQUIRED_FIELDS {
+        let is_present = key
+            .get(field)
+            .and_then(Value::as_str)
+            .is_some_and(|value| !value.is_empty());
+        if !is_present {
+            return Err(KeyValidationError::MissingField(field));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVICE_ACCOUNT_KEY_JSON: &str = include_str!("testdata/service_account_key.json");
+
+    #[test]
+    fn should_accept_complete_key() {
+        assert!(validate_service_account_key_json(SERVICE_ACCOUNT_KEY_JSON).is_ok());
+    }
+
+    #[test]
+    fn should_reject_key_missing_required_field() {
+        for field in REQUIRED_FIELDS {
+            let mut key: serde_json::Map<String, Value> = serde_json::from_str(SERVICE_ACCOUNT_KEY_JSON).unwrap();
+            key.remove(field);
+
+            let result = validate_service_account_key_json(&Value::Object(key).to_string());
+
+            assert!(
+                matches!(result, Err(KeyValidationError::MissingField(missing)) if missing == field),
+                "{}: {:?}",
+                field,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_key_with_empty_field() {
+        let mut key: serde_json::Map<String, Value> = serde_json::from_str(SERVICE_ACCOUNT_KEY_JSON).unwrap();
+        key.insert("private_key".to_string(), Value::from(""));
+
+        let result = validate_service_account_key_json(&Value::Object(key).to_string());
+
+        assert!(matches!(result, Err(KeyValidationError::MissingField("private_key"))));
+    }
+
+    #[test]
+    fn should_reject_truncated_key() {
+        let truncated = &SERVICE_ACCOUNT_KEY_JSON[..SERVICE_ACCOUNT_KEY_JSON.len() / 2];
+
+        let result = validate_service_account_key_json(truncated);
+
+        assert!(matches!(result, Err(KeyValidationError::InvalidJson(_))));
+    }
+}
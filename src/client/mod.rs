@@ -1,18 +1,62 @@
 pub mod response;
 
+#[cfg(feature = "blocking")]
+mod blocking;
+mod circuit_breaker;
+mod clock;
+mod defaults;
+mod inflight;
+mod inspect;
+mod key_validation;
+mod multicast;
 mod oauth;
+#[cfg(feature = "otel")]
+mod otel;
+mod policy;
+#[cfg(feature = "request-signing")]
+mod signing;
+mod token_info;
+mod token_store;
+mod worker;
 
+#[cfg(test)]
+mod tests;
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::{self, Either};
+use futures::stream::{self, StreamExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
 
-use reqwest::header::RETRY_AFTER;
+use crate::client::response::{parse_response_json, FcmResponse, FcmResponseError, HandledOutcome, ValidationResult};
+use crate::message::{
+    canonical::canonicalize, AnalyticsLabel, AndroidConfig, ApnsConfig, FcmOptions, Message, MessageWrapper,
+    NormalizeOptions, Target,
+};
 
-use crate::client::response::FcmResponse;
-use crate::message::{Message, MessageWrapper};
+use self::{
+    circuit_breaker::CircuitBreaker, defaults::MessageDefaults, inflight::InflightTracker, inspect::Inspectors,
+    oauth::OauthClient, response::RetryAfter,
+};
 
-use self::{oauth::OauthClient, response::RetryAfter};
+pub use self::circuit_breaker::CircuitBreakerConfig;
+pub use self::clock::{Clock, SystemClock};
+pub use self::inspect::{RequestInspector, ResponseInspector};
+pub use self::key_validation::{validate_service_account_key_json, KeyValidationError};
+pub use self::multicast::{partition_multicast_results, MulticastPartition};
+pub use self::oauth::{CredentialsSource, OauthError, TokenStrategy};
+pub use self::policy::{BackoffPolicy, SendPolicy};
+pub use self::token_info::{TokenInfo, TopicSubscription};
+pub use self::token_store::TokenStore;
 
-pub use self::oauth::OauthError;
+const FCM_API_BASE_URL: &str = "https://fcm.googleapis.com";
+const IID_API_BASE_URL: &str = "https://iid.googleapis.com";
 
 #[derive(thiserror::Error, Debug)]
 pub enum FcmClientError {
@@ -22,10 +66,47 @@ pub enum FcmClientError {
     Oauth(OauthError),
     #[error("Dotenvy error: {0}")]
     Dotenvy(#[from] dotenvy::Error),
+    /// Not returned when sending, see [FcmResponse::raw_retry_after].
     #[error("Retry-After HTTP header value is not valid string")]
     RetryAfterHttpHeaderIsNotString,
+    /// Not returned when sending, see [FcmResponse::raw_retry_after].
     #[error("Retry-After HTTP header value is not valid, error: {error}, value: {value}")]
     RetryAfterHttpHeaderInvalid { error: chrono::ParseError, value: String },
+    #[error("Circuit breaker is open, message was not sent")]
+    CircuitOpen,
+    #[error("Token cache directory creation failed: {0}")]
+    TokenCacheDirectoryCreatingFailed(std::io::Error),
+    #[error("Sending was cancelled")]
+    Cancelled,
+    /// The FCM request took longer than
+    /// [FcmClientBuilder::fcm_request_timeout] or the timeout of
+    /// [FcmClient::send_with_timeout].
+    #[error("Sending timed out")]
+    Timeout,
+    #[error("Request body serialization failed: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Instance ID request failed with HTTP status {0}")]
+    InstanceIdRequestFailed(u16),
+    #[error("HTTP header is not valid: {0}")]
+    InvalidHeader(String),
+    #[error("URL is not valid: {0}")]
+    InvalidUrl(String),
+    /// FCM responded to [FcmClient::validate] with an error which does
+    /// not tell whether the message is valid.
+    #[error("Message validation failed: {0}")]
+    ValidationFailed(FcmResponseError),
+    #[cfg(feature = "middleware")]
+    #[error("Middleware error: {0}")]
+    Middleware(reqwest_middleware::Error),
+    #[cfg(feature = "request-signing")]
+    #[error("Request signing header name is not valid: {0}")]
+    InvalidSigningHeaderName(String),
+    #[cfg(feature = "blocking")]
+    #[error("Runtime creation failed: {0}")]
+    RuntimeCreatingFailed(std::io::Error),
+    #[cfg(feature = "blocking")]
+    #[error("Blocking method was called from an async context")]
+    BlockingInAsyncContext,
 }
 
 impl FcmClientError {
@@ -37,6 +118,21 @@ impl FcmClientError {
             _ => false,
         }
     }
+
+    /// If this is `true` then the `private_key` of the service account
+    /// key is malformed, for example truncated or not a PKCS #8 PEM
+    /// encoded RSA key.
+    pub fn is_private_key_invalid(&self) -> bool {
+        matches!(self, FcmClientError::Oauth(OauthError::PrivateKeyInvalid(_)))
+    }
+
+    fn is_connect_or_timeout(&self) -> bool {
+        match self {
+            FcmClientError::Timeout => true,
+            FcmClientError::Reqwest(error) => error.is_connect(),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -44,8 +140,32 @@ pub struct FcmClientBuilder {
     service_account_key_json_string: Option<String>,
     service_account_key_json_path: Option<PathBuf>,
     token_cache_json_path: Option<PathBuf>,
+    token_store: Option<Arc<dyn TokenStore>>,
+    project_id: Option<String>,
     fcm_request_timeout: Option<Duration>,
+    http2_prior_knowledge: bool,
+    root_certificates: Vec<reqwest::Certificate>,
     dry_run: Option<bool>,
+    default_android: Option<AndroidConfig>,
+    default_apns: Option<ApnsConfig>,
+    default_android_ttl: Option<Duration>,
+    default_apns_headers: HashMap<String, String>,
+    circuit_breaker: Option<CircuitBreakerConfig>,
+    inspectors: Inspectors,
+    #[cfg(feature = "uuid")]
+    generate_apns_id: bool,
+    auto_refresh_on_401: bool,
+    fallback_service_account_key_json_path: Option<PathBuf>,
+    transport_retry_attempts: u32,
+    sort_json_keys: bool,
+    token_strategy: TokenStrategy,
+    max_concurrent_requests: Option<usize>,
+    default_headers: Vec<(String, String)>,
+    clock: Option<Arc<dyn Clock>>,
+    #[cfg(feature = "middleware")]
+    middleware_client: Option<reqwest_middleware::ClientWithMiddleware>,
+    #[cfg(feature = "request-signing")]
+    request_signing: Option<signing::RequestSigningConfig>,
 }
 
 impl FcmClientBuilder {
@@ -55,7 +175,8 @@ impl FcmClientBuilder {
 
     /// Set path to the service account key JSON file. Default is to use
     /// path from the `GOOGLE_APPLICATION_CREDENTIALS` environment variable
-    /// (which can be also located in `.env` file).
+    /// (which can be also located in `.env` file). The environment variable
+    /// can also contain the service account key JSON itself.
     pub fn service_account_key_json_path(mut self, service_account_key_json_path: impl AsRef<Path>) -> Self {
         self.service_account_key_json_path = Some(service_account_key_json_path.as_ref().to_path_buf());
         self
@@ -71,12 +192,58 @@ impl FcmClientBuilder {
         self
     }
 
+    /// Use only HTTP/2 for FCM requests, without negotiating the
+    /// protocol version first. Concurrent sends are then multiplexed
+    /// over one connection. Default is `false`.
+    ///
+    /// Requests fail if something between the client and FCM, for
+    /// example a proxy, supports only HTTP/1.1. Requests for OAuth
+    /// tokens and the client set with `middleware_client` are not
+    /// affected.
+    pub fn http2_prior_knowledge(mut self, http2_prior_knowledge: bool) -> Self {
+        self.http2_prior_knowledge = http2_prior_knowledge;
+        self
+    }
+
+    /// Trust `certificate` in addition to the default root certificates
+    /// for FCM requests, for example the internal CA of a proxy which
+    /// intercepts TLS traffic. Can be called multiple times.
+    ///
+    /// Requests for OAuth tokens are sent with a separate HTTP client
+    /// which is not affected, so the proxy must let those requests
+    /// through or the system root certificates must trust the CA. The
+    /// client set with `middleware_client` is not affected either.
+    pub fn add_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
     /// Set path to the token cache JSON file. Default is no token cache JSON file.
+    ///
+    /// Missing parent directories are created when the client is built.
     pub fn token_cache_json_path(mut self, token_cache_json_path: impl AsRef<Path>) -> Self {
         self.token_cache_json_path = Some(token_cache_json_path.as_ref().to_path_buf());
         self
     }
 
+    /// Set custom storage for OAuth tokens. This overrides
+    /// `token_cache_json_path`. Default is no custom storage.
+    pub fn token_store(mut self, token_store: Arc<dyn TokenStore>) -> Self {
+        self.token_store = Some(token_store);
+        self
+    }
+
+    /// Send messages to project `project_id` instead of the project of
+    /// the service account key, for example when one service account
+    /// sends on behalf of multiple projects. The service account must
+    /// still be authorized to send messages for that project, otherwise
+    /// FCM responds with a permission error. Also applies to the
+    /// fallback service account key.
+    pub fn project_id(mut self, project_id: impl Into<String>) -> Self {
+        self.project_id = Some(project_id.into());
+        self
+    }
+
     /// Set service account key JSON. Default is to use
     /// path from the `GOOGLE_APPLICATION_CREDENTIALS` environment variable
     /// (which can be also located in `.env` file).
@@ -92,6 +259,176 @@ impl FcmClientBuilder {
         self
     }
 
+    /// Set `AndroidConfig` which is used for every message which does not
+    /// have `android` set. Fields are not merged: `android` of the
+    /// message is used as is if the message has it. Default is none.
+    ///
+    /// [FcmClientBuilder::default_android_ttl] is applied after this.
+    pub fn default_android(mut self, default_android: AndroidConfig) -> Self {
+        self.default_android = Some(default_android);
+        self
+    }
+
+    /// Set `ApnsConfig` which is used for every message which does not
+    /// have `apns` set. Fields are not merged: `apns` of the message is
+    /// used as is if the message has it. Default is none.
+    ///
+    /// [FcmClientBuilder::default_apns_headers] is applied after this.
+    pub fn default_apns(mut self, default_apns: ApnsConfig) -> Self {
+        self.default_apns = Some(default_apns);
+        self
+    }
+
+    /// Set TTL which is added to every message which does not have
+    /// `android.ttl` set. `AndroidConfig` is created for messages which
+    /// do not have it. Default is no TTL.
+    pub fn default_android_ttl(mut self, default_android_ttl: Duration) -> Self {
+        self.default_android_ttl = Some(default_android_ttl);
+        self
+    }
+
+    /// Set APNs headers (for example `apns-priority` and
+    /// `apns-push-type`) which are added to every message. Headers set in
+    /// the message are kept. `ApnsConfig` is created for messages which
+    /// do not have it. Default is no headers.
+    pub fn default_apns_headers(mut self, default_apns_headers: HashMap<String, String>) -> Self {
+        self.default_apns_headers = default_apns_headers;
+        self
+    }
+
+    /// Enable circuit breaker which stops sending messages for a while
+    /// when FCM keeps responding with retryable errors. Default is no
+    /// circuit breaker.
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Set function which is called with every FCM request just before
+    /// it is sent. Useful for logging and testing.
+    pub fn inspect(mut self, inspector: RequestInspector) -> Self {
+        self.inspectors.request = Some(inspector);
+        self
+    }
+
+    /// Set function which is called with every FCM response before the
+    /// response body is read. Useful for logging and testing.
+    pub fn inspect_response(mut self, inspector: ResponseInspector) -> Self {
+        self.inspectors.response = Some(inspector);
+        self
+    }
+
+    /// Add random `apns-id` header to every message which does not have
+    /// it. `ApnsConfig` is created for messages which do not have it.
+    /// Default is `false`.
+    #[cfg(feature = "uuid")]
+    pub fn generate_apns_id(mut self, generate_apns_id: bool) -> Self {
+        self.generate_apns_id = generate_apns_id;
+        self
+    }
+
+    /// When FCM responds with HTTP status 401, refresh the access token
+    /// and send the message again. The message is sent again only once,
+    /// so the second 401 response is returned as is. Default is `false`.
+    pub fn auto_refresh_on_401(mut self, auto_refresh_on_401: bool) -> Self {
+        self.auto_refresh_on_401 = auto_refresh_on_401;
+        self
+    }
+
+    /// Set path to the service account key JSON file which is used when
+    /// sending with the primary service account fails because of
+    /// authentication. The message is then sent to the Firebase project
+    /// of the fallback service account. Default is no fallback.
+    ///
+    /// Fallback is used only if getting the access token fails or FCM
+    /// responds with HTTP status 401 (after `auto_refresh_on_401` retry
+    /// if that is enabled). Other errors, including 403 responses, are
+    /// returned as is. Tokens of the fallback service account are not
+    /// cached to `token_cache_json_path`.
+    pub fn fallback_service_account_key_json_path(
+        mut self,
+        fallback_service_account_key_json_path: impl AsRef<Path>,
+    ) -> Self {
+        self.fallback_service_account_key_json_path =
+            Some(fallback_service_account_key_json_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set how many times a request is sent again when it fails
+    /// because connecting to FCM failed or the request timed out. Other
+    /// errors are not retried. Default is `0`.
+    ///
+    /// Note that FCM may have received the message when the request
+    /// timed out, so retrying may deliver the message twice.
+    pub fn transport_retry_attempts(mut self, transport_retry_attempts: u32) -> Self {
+        self.transport_retry_attempts = transport_retry_attempts;
+        self
+    }
+
+    /// Sort keys of all JSON objects in the request body, so that equal
+    /// messages always have byte-for-byte equal request bodies. Useful
+    /// for snapshot tests and request signing, as key order of `data`
+    /// and other `serde_json::Value` fields depends on how they were
+    /// constructed. Default is `false`.
+    pub fn sort_json_keys(mut self, sort_json_keys: bool) -> Self {
+        self.sort_json_keys = sort_json_keys;
+        self
+    }
+
+    /// Set how the access token is got for FCM requests. Default is
+    /// [TokenStrategy::PerRequest].
+    pub fn token_strategy(mut self, token_strategy: TokenStrategy) -> Self {
+        self.token_strategy = token_strategy;
+        self
+    }
+
+    /// Limit how many messages are sent at the same time by all methods
+    /// of the client together, for example when multiple
+    /// [FcmClient::send_multicast] calls run concurrently. Sends wait
+    /// until there is room. Default is no limit.
+    pub fn max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.max_concurrent_requests = Some(max_concurrent_requests);
+        self
+    }
+
+    /// Add HTTP header `name` with `value` to every FCM request, for
+    /// example to opt in to experimental API features. `Authorization`
+    /// and `Content-Type` headers can not be overridden. Invalid headers
+    /// fail [FcmClientBuilder::build] with [FcmClientError::InvalidHeader].
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.default_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set source of the current time, which is used for `Retry-After`
+    /// wait times, [TokenStrategy::Cached] token expiration and the
+    /// cool-down of the circuit breaker. Useful for tests. Default is
+    /// [SystemClock].
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Send messages using `middleware_client`, for example for tracing.
+    /// Requests for OAuth tokens do not use it. `fcm_request_timeout` is
+    /// not applied to this client. Default is to use a plain
+    /// `reqwest::Client`.
+    #[cfg(feature = "middleware")]
+    pub fn with_middleware_client(mut self, middleware_client: reqwest_middleware::ClientWithMiddleware) -> Self {
+        self.middleware_client = Some(middleware_client);
+        self
+    }
+
+    /// Add header `header_name` with lowercase hexadecimal HMAC-SHA256
+    /// of the request body using `key` to every FCM request, for example
+    /// for an internal gateway which checks the signature. Default is no
+    /// signature.
+    #[cfg(feature = "request-signing")]
+    pub fn sign_requests(mut self, key: Vec<u8>, header_name: String) -> Self {
+        self.request_signing = Some(signing::RequestSigningConfig { key, header_name });
+        self
+    }
+
     pub async fn build(self) -> Result<FcmClient, FcmClientError> {
         FcmClient::new_from_builder(self).await
     }
@@ -101,7 +438,33 @@ impl FcmClientBuilder {
 pub struct FcmClient {
     http_client: reqwest::Client,
     oauth_client: OauthClient,
+    fallback_oauth_client: Option<OauthClient>,
+    credentials_source: CredentialsSource,
+    api_base_url: String,
+    /// Project which overrides the project of the service account key.
+    project_id: Option<String>,
+    /// `messages:send` URL of the project of `oauth_client`.
+    send_url: String,
+    /// `messages:send` URL of the project of `fallback_oauth_client`.
+    fallback_send_url: String,
+    iid_api_base_url: String,
     pub dry_run: bool,
+    defaults: MessageDefaults,
+    circuit_breaker: Option<CircuitBreaker>,
+    inspectors: Inspectors,
+    inflight: InflightTracker,
+    concurrency_limit: Option<Semaphore>,
+    clock: Arc<dyn Clock>,
+    default_headers: HeaderMap,
+    auto_refresh_on_401: bool,
+    transport_retry_attempts: u32,
+    sort_json_keys: bool,
+    #[cfg(feature = "middleware")]
+    middleware_client: Option<reqwest_middleware::ClientWithMiddleware>,
+    #[cfg(feature = "request-signing")]
+    request_signer: Option<signing::RequestSigner>,
+    #[cfg(feature = "blocking")]
+    blocking_runtime: blocking::BlockingRuntime,
 }
 
 impl FcmClient {
@@ -109,82 +472,761 @@ impl FcmClient {
         FcmClientBuilder::new()
     }
 
-    async fn new_from_builder(fcm_builder: FcmClientBuilder) -> Result<Self, FcmClientError> {
+    async fn new_from_builder(mut fcm_builder: FcmClientBuilder) -> Result<Self, FcmClientError> {
+        if let Some(parent) = fcm_builder
+            .token_cache_json_path
+            .as_deref()
+            .and_then(Path::parent)
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(FcmClientError::TokenCacheDirectoryCreatingFailed)?;
+        }
+
+        let token_cache_json_path = fcm_builder.token_cache_json_path.take();
+        let token_store = fcm_builder.token_store.take();
+        let (credentials_source, oauth_client) = if let Some(key_json) =
+            fcm_builder.service_account_key_json_string.take()
+        {
+            let oauth_client = OauthClient::create_with_string_key(key_json, token_cache_json_path, token_store).await;
+            (CredentialsSource::JsonString, oauth_client)
+        } else if let Some(path) = fcm_builder.service_account_key_json_path.take() {
+            let oauth_client = OauthClient::create_with_key_file(path, token_cache_json_path, token_store).await;
+            (CredentialsSource::ExplicitPath, oauth_client)
+        } else {
+            let credentials = dotenvy::var("GOOGLE_APPLICATION_CREDENTIALS")?;
+            if credentials.trim_start().starts_with('{') {
+                let oauth_client =
+                    OauthClient::create_with_string_key(credentials, token_cache_json_path, token_store).await;
+                (CredentialsSource::EnvJson, oauth_client)
+            } else {
+                let oauth_client =
+                    OauthClient::create_with_key_file(credentials.into(), token_cache_json_path, token_store).await;
+                (CredentialsSource::EnvPath, oauth_client)
+            }
+        };
+        let oauth_client = oauth_client.map_err(FcmClientError::Oauth)?;
+
+        let fallback_oauth_client = match fcm_builder.fallback_service_account_key_json_path.take() {
+            Some(path) => Some(
+                OauthClient::create_with_key_file(path, None, None)
+                    .await
+                    .map_err(FcmClientError::Oauth)?,
+            ),
+            None => None,
+        };
+
+        let mut client = Self::new_with_oauth_client(fcm_builder, oauth_client, credentials_source)?;
+        if let Some(fallback_oauth_client) = fallback_oauth_client {
+            client.set_fallback_oauth_client(fallback_oauth_client);
+        }
+        Ok(client)
+    }
+
+    fn set_fallback_oauth_client(&mut self, mut fallback_oauth_client: OauthClient) {
+        fallback_oauth_client.set_token_strategy(self.oauth_client.token_strategy());
+        fallback_oauth_client.set_clock(self.clock.clone());
+        self.fallback_send_url = self.messages_send_url(&fallback_oauth_client);
+        self.fallback_oauth_client = Some(fallback_oauth_client);
+    }
+
+    #[cfg(test)]
+    fn set_api_base_url(&mut self, api_base_url: impl Into<String>) {
+        self.api_base_url = api_base_url.into();
+        self.iid_api_base_url = self.api_base_url.clone();
+        self.send_url = self.messages_send_url(&self.oauth_client);
+        if let Some(fallback_oauth_client) = &self.fallback_oauth_client {
+            self.fallback_send_url = self.messages_send_url(fallback_oauth_client);
+        }
+    }
+
+    /// `messages:send` URL of the overriding project or the project of
+    /// `oauth_client`.
+    fn messages_send_url(&self, oauth_client: &OauthClient) -> String {
+        let project_id = self
+            .project_id
+            .as_deref()
+            .unwrap_or_else(|| oauth_client.get_project_id());
+        messages_send_url(&self.api_base_url, project_id)
+    }
+
+    fn new_with_oauth_client(
+        fcm_builder: FcmClientBuilder,
+        mut oauth_client: OauthClient,
+        credentials_source: CredentialsSource,
+    ) -> Result<Self, FcmClientError> {
         let builder = reqwest::ClientBuilder::new();
         let builder = if let Some(timeout) = fcm_builder.fcm_request_timeout {
             builder.timeout(timeout)
         } else {
             builder
         };
+        let builder = if fcm_builder.http2_prior_knowledge {
+            builder.http2_prior_knowledge()
+        } else {
+            builder
+        };
+        let builder = fcm_builder
+            .root_certificates
+            .into_iter()
+            .fold(builder, |builder, certificate| {
+                builder.add_root_certificate(certificate)
+            });
         let http_client = builder.build()?;
 
-        let oauth_client = if let Some(key_json) = fcm_builder.service_account_key_json_string {
-            OauthClient::create_with_string_key(key_json, fcm_builder.token_cache_json_path)
-                .await
-                .map_err(FcmClientError::Oauth)?
-        } else {
-            let service_account_key_path = if let Some(path) = fcm_builder.service_account_key_json_path {
-                path
-            } else {
-                dotenvy::var("GOOGLE_APPLICATION_CREDENTIALS")?.into()
-            };
+        let clock = fcm_builder.clock.unwrap_or_else(|| Arc::new(SystemClock));
+        oauth_client.set_token_strategy(fcm_builder.token_strategy);
+        oauth_client.set_clock(clock.clone());
+        let project_id = fcm_builder.project_id;
+        let send_url = messages_send_url(
+            FCM_API_BASE_URL,
+            project_id.as_deref().unwrap_or_else(|| oauth_client.get_project_id()),
+        );
 
-            OauthClient::create_with_key_file(service_account_key_path, fcm_builder.token_cache_json_path)
-                .await
-                .map_err(FcmClientError::Oauth)?
+        let mut default_headers = HeaderMap::new();
+        for (name, value) in &fcm_builder.default_headers {
+            let name =
+                HeaderName::from_bytes(name.as_bytes()).map_err(|_| FcmClientError::InvalidHeader(name.clone()))?;
+            let value = HeaderValue::from_str(value).map_err(|_| FcmClientError::InvalidHeader(name.to_string()))?;
+            default_headers.append(name, value);
+        }
+
+        #[cfg(feature = "request-signing")]
+        let request_signer = match fcm_builder.request_signing {
+            Some(config) => {
+                let header_name = config.header_name.clone();
+                Some(signing::RequestSigner::new(config).ok_or(FcmClientError::InvalidSigningHeaderName(header_name))?)
+            }
+            None => None,
         };
 
         Ok(FcmClient {
             http_client,
             oauth_client,
+            fallback_oauth_client: None,
+            credentials_source,
+            api_base_url: FCM_API_BASE_URL.to_string(),
+            project_id,
+            send_url,
+            fallback_send_url: String::new(),
+            iid_api_base_url: IID_API_BASE_URL.to_string(),
             dry_run: fcm_builder.dry_run.unwrap_or(false),
+            defaults: MessageDefaults {
+                android: fcm_builder.default_android,
+                apns: fcm_builder.default_apns,
+                android_ttl: fcm_builder.default_android_ttl,
+                apns_headers: fcm_builder.default_apns_headers,
+                #[cfg(feature = "uuid")]
+                generate_apns_id: fcm_builder.generate_apns_id,
+            },
+            circuit_breaker: fcm_builder
+                .circuit_breaker
+                .map(|config| CircuitBreaker::new(config, clock.clone())),
+            inspectors: fcm_builder.inspectors,
+            inflight: InflightTracker::default(),
+            concurrency_limit: fcm_builder
+                .max_concurrent_requests
+                .map(|max_concurrent_requests| Semaphore::new(max_concurrent_requests.max(1))),
+            clock,
+            default_headers,
+            auto_refresh_on_401: fcm_builder.auto_refresh_on_401,
+            transport_retry_attempts: fcm_builder.transport_retry_attempts,
+            sort_json_keys: fcm_builder.sort_json_keys,
+            #[cfg(feature = "middleware")]
+            middleware_client: fcm_builder.middleware_client,
+            #[cfg(feature = "request-signing")]
+            request_signer,
+            #[cfg(feature = "blocking")]
+            blocking_runtime: blocking::BlockingRuntime::default(),
         })
     }
 
-    pub async fn send(&self, message: impl AsRef<Message>) -> Result<FcmResponse, FcmClientError> {
-        let access_token = self
-            .oauth_client
+    /// Where the service account key of this client is from. Useful for
+    /// startup diagnostics.
+    pub fn credentials_source(&self) -> CredentialsSource {
+        self.credentials_source
+    }
+
+    /// OAuth 2.0 access token with the Firebase Cloud Messaging scope.
+    ///
+    /// The token is a bearer token: anyone who has it can send messages
+    /// to all users of the Firebase project until the token expires.
+    /// Do not log it or send it to other services than Google APIs.
+    pub async fn access_token(&self) -> Result<String, FcmClientError> {
+        self.oauth_client
             .get_access_token()
             .await
-            .map_err(FcmClientError::Oauth)?;
+            .map_err(FcmClientError::Oauth)
+    }
 
-        // https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages/send
-        let url = format!(
-            "https://fcm.googleapis.com/v1/projects/{}/messages:send",
-            self.oauth_client.get_project_id()
-        );
+    /// OAuth 2.0 access token for `scopes` using the service account of
+    /// this client. Useful for calling other Google APIs (for example
+    /// Firestore) without creating another authenticator.
+    ///
+    /// Check security notes of [FcmClient::access_token]. The service
+    /// account has access to everything the scopes allow.
+    pub async fn access_token_for_scopes(&self, scopes: &[&str]) -> Result<String, FcmClientError> {
+        self.oauth_client
+            .get_access_token_for_scopes(scopes)
+            .await
+            .map_err(FcmClientError::Oauth)
+    }
 
-        let request = self
+    /// Get information about registration `token` from the Instance ID
+    /// API, including the topics it is subscribed to. Useful for debugging
+    /// topic message delivery.
+    ///
+    /// Default headers of the client are not sent to the Instance ID API.
+    pub async fn get_token_info(&self, token: &str) -> Result<TokenInfo, FcmClientError> {
+        let access_token = self.access_token().await?;
+        let mut url = reqwest::Url::parse(&self.iid_api_base_url)
+            .map_err(|_| FcmClientError::InvalidUrl(self.iid_api_base_url.clone()))?;
+        url.path_segments_mut()
+            .map_err(|()| FcmClientError::InvalidUrl(self.iid_api_base_url.clone()))?
+            .pop_if_empty()
+            .extend(["iid", "info", token]);
+        let response = self
             .http_client
-            .post(&url)
+            .get(url)
+            .query(&[("details", "true")])
             .bearer_auth(access_token)
-            .json(&MessageWrapper::new(message.as_ref(), self.dry_run))
-            .build()?;
+            .header("access_token_auth", "true")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(FcmClientError::InstanceIdRequestFailed(response.status().as_u16()));
+        }
+        Ok(response.json().await?)
+    }
+
+    pub async fn send(&self, message: impl AsRef<Message>) -> Result<FcmResponse, FcmClientError> {
+        self.send_message(Cow::Borrowed(message.as_ref()), None, self.dry_run)
+            .await
+    }
+
+    /// Same as [FcmClient::send] but first normalizes `message` in place
+    /// (see [Message::normalize_with_clock], using the clock of the
+    /// client) and adds client level defaults (for example
+    /// `default_android_ttl`) to it, so that `message` is what was
+    /// actually sent.
+    pub async fn send_normalizing(&self, message: &mut Message) -> Result<FcmResponse, FcmClientError> {
+        message.apply_normalization(self.clock.now(), NormalizeOptions::default());
+        let with_defaults = match self.defaults.apply(Cow::Borrowed(&*message)) {
+            Cow::Owned(with_defaults) => Some(with_defaults),
+            Cow::Borrowed(_) => None,
+        };
+        if let Some(with_defaults) = with_defaults {
+            *message = with_defaults;
+        }
+        self.send(&*message).await
+    }
+
+    /// Send a message of a custom type which serializes to the FCM
+    /// [message](https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages#resource:-message)
+    /// JSON. The message is wrapped to the request body like [Message]s
+    /// are, see [crate::message::wrap_message].
+    ///
+    /// Client level defaults (for example `default_android_ttl`) are not
+    /// added to the message. As the target of the message is not known,
+    /// [FcmResponse::recommended_error_handling_action] assumes that it
+    /// is a token.
+    pub async fn send_serializable(&self, message: &impl serde::Serialize) -> Result<FcmResponse, FcmClientError> {
+        let body = request_body(&MessageWrapper::new(message, self.dry_run), self.sort_json_keys)?;
+        self.send_body(SendRequest {
+            body: &body,
+            target: None,
+            headers: None,
+            dry_run: self.dry_run,
+        })
+        .await
+    }
+
+    /// Check `message` with FCM without delivering it, like sending with
+    /// [FcmClientBuilder::dry_run] enabled. Client level defaults are
+    /// added to the message as when sending.
+    ///
+    /// [ValidationResult::Invalid] contains the field violations of an
+    /// [FcmResponseError::InvalidArgument] response. Other FCM errors,
+    /// for example [FcmResponseError::QuotaExceeded], are returned as
+    /// [FcmClientError::ValidationFailed].
+    pub async fn validate(&self, message: &Message) -> Result<ValidationResult, FcmClientError> {
+        let response = self.send_message(Cow::Borrowed(message), None, true).await?;
+        match response.error() {
+            None => Ok(ValidationResult::Valid),
+            Some(FcmResponseError::InvalidArgument) => Ok(ValidationResult::Invalid(response.field_violations())),
+            Some(error) => Err(FcmClientError::ValidationFailed(error)),
+        }
+    }
+
+    /// Same as [FcmClient::send] but adds `headers` to the request. The
+    /// headers override the headers set with
+    /// [FcmClientBuilder::default_header]. `Authorization` and
+    /// `Content-Type` headers can not be overridden.
+    pub async fn send_with_headers(
+        &self,
+        message: &Message,
+        headers: HeaderMap,
+    ) -> Result<FcmResponse, FcmClientError> {
+        self.send_message(Cow::Borrowed(message), Some(&headers), self.dry_run)
+            .await
+    }
+
+    /// Same as [FcmClient::send] but also returns how long sending took.
+    /// The duration is wall-clock time of the whole call, so it includes
+    /// getting the access token (usually from cache) and retries.
+    pub async fn send_timed(&self, message: &Message) -> (Result<FcmResponse, FcmClientError>, Duration) {
+        let started = Instant::now();
+        let result = self.send(message).await;
+        (result, started.elapsed())
+    }
+
+    /// Same as [FcmClient::send] but [FcmClientError::Timeout] is returned
+    /// if sending takes longer than `timeout`. Unlike
+    /// [FcmClientBuilder::fcm_request_timeout], the timeout includes
+    /// getting the access token. FCM may have already received the message.
+    pub async fn send_with_timeout(&self, message: &Message, timeout: Duration) -> Result<FcmResponse, FcmClientError> {
+        tokio::time::timeout(timeout, self.send(message))
+            .await
+            .unwrap_or(Err(FcmClientError::Timeout))
+    }
+
+    /// Send `message` with timeout and retries of `policy`.
+    ///
+    /// Timeouts, connection errors and retryable FCM errors are retried.
+    /// The wait time before retrying is from the `Retry-After` header of
+    /// the response if FCM sets it and from `policy.backoff` otherwise.
+    /// Returns the result of the last attempt.
+    pub async fn send_with_policy(&self, message: &Message, policy: SendPolicy) -> Result<FcmResponse, FcmClientError> {
+        let mut retry = 0;
+        loop {
+            let result = match policy.timeout {
+                Some(timeout) => self.send_with_timeout(message, timeout).await,
+                None => self.send(message).await,
+            };
+            if retry + 1 >= policy.max_attempts {
+                return result;
+            }
+            let wait_time = match self.retry_wait_time(&result, retry, policy.backoff) {
+                Some(wait_time) => wait_time,
+                None => return result,
+            };
+            tokio::time::sleep(wait_time).await;
+            retry += 1;
+        }
+    }
+
+    /// Send `message` and interpret the response. If sending fails
+    /// without a response from FCM (for example because of a network
+    /// error), `ok` is `false` and the other fields are `None`; use
+    /// [FcmClient::send] if the error is needed.
+    pub async fn send_handled(&self, message: &Message) -> HandledOutcome {
+        match self.send(message).await {
+            Ok(response) => HandledOutcome::new(&response, &message.target, self.clock.now()),
+            Err(_) => HandledOutcome::default(),
+        }
+    }
+
+    /// Same as [FcmClient::send] but the HTTP request is aborted and
+    /// [FcmClientError::Cancelled] is returned when `cancellation_token`
+    /// is cancelled. FCM may have already received the message.
+    pub async fn send_cancellable(
+        &self,
+        message: impl AsRef<Message>,
+        cancellation_token: CancellationToken,
+    ) -> Result<FcmResponse, FcmClientError> {
+        let send = self.send(message);
+        let cancelled = cancellation_token.cancelled();
+        futures::pin_mut!(send, cancelled);
+        match future::select(send, cancelled).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => Err(FcmClientError::Cancelled),
+        }
+    }
+
+    /// Same as [FcmClient::send] but takes ownership of the message, so
+    /// the message is not cloned when client level defaults are added
+    /// to it.
+    pub async fn send_owned(&self, message: Message) -> Result<FcmResponse, FcmClientError> {
+        self.send_message(Cow::Owned(message), None, self.dry_run).await
+    }
+
+    /// Amount of messages which are currently being sent.
+    pub fn inflight_count(&self) -> usize {
+        self.inflight.count()
+    }
+
+    /// Wait until there are no messages being sent. Useful for graceful
+    /// shutdown, but note that sends started while waiting are also
+    /// waited for.
+    pub async fn drain(&self) {
+        self.inflight.wait_idle().await
+    }
+
+    /// Send messages one at a time, skipping messages which are
+    /// duplicates of an earlier message in `messages`.
+    ///
+    /// Messages are duplicates if their JSON forms are equal, so every
+    /// field including target and data must be equal. Key order of JSON
+    /// objects (for example `data`) does not matter.
+    ///
+    /// Returns the sent messages with their results in the original order.
+    /// A message which can not be serialized is not sent and has
+    /// [FcmClientError::Serialization] as its result.
+    pub async fn send_batch_dedup(
+        &self,
+        messages: Vec<Message>,
+    ) -> Vec<(Message, Result<FcmResponse, FcmClientError>)> {
+        let mut seen = HashSet::new();
+        let mut results = Vec::new();
+        for message in messages {
+            let key = match serde_json::to_value(&message) {
+                Ok(json) => canonicalize(json).to_string(),
+                Err(error) => {
+                    results.push((message, Err(error.into())));
+                    continue;
+                }
+            };
+            if seen.insert(key) {
+                let result = self.send(&message).await;
+                results.push((message, result));
+            }
+        }
+        results
+    }
+
+    /// Send `message` to every token in `tokens`, replacing the target of
+    /// the message. At most `concurrency` messages are sent at the same
+    /// time.
+    ///
+    /// Returns the tokens with their results in the original order.
+    pub async fn send_multicast(
+        &self,
+        message: &Message,
+        tokens: &[String],
+        concurrency: usize,
+    ) -> Vec<(String, Result<FcmResponse, FcmClientError>)> {
+        stream::iter(tokens)
+            .map(|token| async move {
+                let mut message = message.clone();
+                message.target = Target::Token(token.clone());
+                (token.clone(), self.send_owned(message).await)
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Same as [FcmClient::send_multicast] but sets
+    /// `fcm_options.analytics_label` of every sent message, for example
+    /// to segment analytics data by campaign batch.
+    pub async fn send_multicast_with_analytics_label(
+        &self,
+        message: &Message,
+        tokens: &[String],
+        concurrency: usize,
+        analytics_label: AnalyticsLabel,
+    ) -> Vec<(String, Result<FcmResponse, FcmClientError>)> {
+        let mut message = message.clone();
+        message.fcm_options = Some(FcmOptions { analytics_label });
+        self.send_multicast(&message, tokens, concurrency).await
+    }
 
-        let response = self.http_client.execute(request).await?;
-        let retry_after = response.headers().get(RETRY_AFTER);
-        let retry_after = if let Some(header_value) = retry_after {
-            let header_str = header_value
-                .to_str()
-                .map_err(|_| FcmClientError::RetryAfterHttpHeaderIsNotString)?;
-            let value =
-                header_str
-                    .parse::<RetryAfter>()
-                    .map_err(|error| FcmClientError::RetryAfterHttpHeaderInvalid {
-                        error,
-                        value: header_str.to_string(),
-                    })?;
-            Some(value)
+    /// Send `message` to every token in `tokens` like
+    /// [FcmClient::send_multicast] and then resend to the tokens whose
+    /// sending failed with a retryable error until sending to all tokens
+    /// has either succeeded or failed permanently, or `max_attempts`
+    /// attempts have been made. `0` is the same as `1`.
+    ///
+    /// Before each retry round the client waits for the longest
+    /// `Retry-After` of the failed responses. Responses without
+    /// `Retry-After`, timeouts and connection errors wait with exponential
+    /// backoff from one second. Other errors, for example unregistered
+    /// tokens, are never retried.
+    ///
+    /// Returns the tokens with their final results in the original order.
+    pub async fn send_multicast_with_retry(
+        &self,
+        message: &Message,
+        tokens: &[String],
+        max_attempts: u32,
+    ) -> Vec<(String, Result<FcmResponse, FcmClientError>)> {
+        let backoff = SendPolicy::default().backoff;
+        let mut results = self.send_multicast(message, tokens, tokens.len()).await;
+        for retry in 0..max_attempts.saturating_sub(1) {
+            let mut wait_time = None;
+            let mut retry_indexes = Vec::new();
+            for (index, (_, result)) in results.iter().enumerate() {
+                let result_wait_time = match self.retry_wait_time(result, retry, backoff) {
+                    Some(wait_time) => wait_time,
+                    None => continue,
+                };
+                wait_time = wait_time.max(Some(result_wait_time));
+                retry_indexes.push(index);
+            }
+            let wait_time = match wait_time {
+                Some(wait_time) => wait_time,
+                None => break,
+            };
+            tokio::time::sleep(wait_time).await;
+
+            let retry_tokens: Vec<String> = retry_indexes.iter().map(|&index| results[index].0.clone()).collect();
+            let retry_results = self.send_multicast(message, &retry_tokens, retry_tokens.len()).await;
+            for (index, (_, result)) in retry_indexes.into_iter().zip(retry_results) {
+                results[index].1 = result;
+            }
+        }
+        results
+    }
+
+    /// Default headers of the client overridden by `headers`, without
+    /// the headers which the client sets itself. With the `otel` feature
+    /// the trace context headers of the current span are added.
+    fn request_headers(&self, headers: Option<&HeaderMap>) -> HeaderMap {
+        let mut request_headers = self.default_headers.clone();
+        if let Some(headers) = headers {
+            request_headers.extend(headers.clone());
+        }
+        request_headers.remove(AUTHORIZATION);
+        request_headers.remove(CONTENT_TYPE);
+        #[cfg(feature = "otel")]
+        otel::inject_trace_context(&mut request_headers);
+        request_headers
+    }
+
+    /// Wait time before retry number `retry` (starting from `0`) of a
+    /// send which ended with `result`, or `None` if sending again does
+    /// not help. Only retryable FCM errors, timeouts and connection
+    /// errors are retried, like in [FcmClient::execute_with_transport_retry].
+    /// The wait time is from the `Retry-After` header of the response if
+    /// FCM sets it and from `backoff` otherwise.
+    fn retry_wait_time(
+        &self,
+        result: &Result<FcmResponse, FcmClientError>,
+        retry: u32,
+        backoff: BackoffPolicy,
+    ) -> Option<Duration> {
+        match result {
+            Ok(response) if response.error().is_some_and(|error| error.is_retryable()) => Some(
+                response
+                    .retry_after()
+                    .map_or_else(|| backoff.delay(retry), |retry_after| self.wait_time(retry_after)),
+            ),
+            Err(error) if error.is_connect_or_timeout() => Some(backoff.delay(retry)),
+            _ => None,
+        }
+    }
+
+    /// Wait time of `retry_after` using the clock of the client.
+    fn wait_time(&self, retry_after: &RetryAfter) -> Duration {
+        retry_after.wait_time_with_clock(self.clock.as_ref())
+    }
+
+    async fn send_message(
+        &self,
+        message: Cow<'_, Message>,
+        headers: Option<&HeaderMap>,
+        dry_run: bool,
+    ) -> Result<FcmResponse, FcmClientError> {
+        let message = self.defaults.apply(message);
+        let body = request_body(&MessageWrapper::new(message.as_ref(), dry_run), self.sort_json_keys)?;
+        self.send_body(SendRequest {
+            body: &body,
+            target: Some(&message.target),
+            headers,
+            dry_run,
+        })
+        .await
+    }
+
+    async fn send_body(&self, request: SendRequest<'_>) -> Result<FcmResponse, FcmClientError> {
+        let _inflight = self.inflight.start();
+        let _permit = match &self.concurrency_limit {
+            Some(concurrency_limit) => Some(concurrency_limit.acquire().await.expect("Semaphore is never closed")),
+            None => None,
+        };
+
+        let circuit_breaker = match &self.circuit_breaker {
+            Some(circuit_breaker) => circuit_breaker,
+            None => return self.send_request(&request).await,
+        };
+
+        if !circuit_breaker.allow_request() {
+            return Err(FcmClientError::CircuitOpen);
+        }
+
+        let result = self.send_request(&request).await;
+        let is_failure = match &result {
+            Ok(response) => response.error().is_some_and(|error| error.is_retryable()),
+            Err(error) => matches!(error, FcmClientError::Reqwest(_) | FcmClientError::Timeout),
+        };
+        if is_failure {
+            circuit_breaker.record_failure();
         } else {
-            None
+            circuit_breaker.record_success();
+        }
+        result
+    }
+
+    async fn send_request(&self, request: &SendRequest<'_>) -> Result<FcmResponse, FcmClientError> {
+        let result = self
+            .send_request_with_oauth_client(request, &self.oauth_client, &self.send_url)
+            .await;
+        let is_auth_failure = match &result {
+            Ok(response) => response.http_status_code() == 401,
+            Err(error) => matches!(error, FcmClientError::Oauth(_)),
+        };
+        match &self.fallback_oauth_client {
+            Some(fallback_oauth_client) if is_auth_failure => {
+                self.send_request_with_oauth_client(request, fallback_oauth_client, &self.fallback_send_url)
+                    .await
+            }
+            _ => result,
+        }
+    }
+
+    async fn send_request_with_oauth_client(
+        &self,
+        request: &SendRequest<'_>,
+        oauth_client: &OauthClient,
+        send_url: &str,
+    ) -> Result<FcmResponse, FcmClientError> {
+        let access_token = oauth_client.get_access_token().await.map_err(FcmClientError::Oauth)?;
+        let response = self
+            .send_request_with_access_token(request, send_url, access_token)
+            .await?;
+
+        if self.auto_refresh_on_401 && response.http_status_code() == 401 {
+            let access_token = oauth_client
+                .force_refresh_access_token()
+                .await
+                .map_err(FcmClientError::Oauth)?;
+            return self
+                .send_request_with_access_token(request, send_url, access_token)
+                .await;
+        }
+
+        Ok(response)
+    }
+
+    async fn send_request_with_access_token(
+        &self,
+        send_request: &SendRequest<'_>,
+        send_url: &str,
+        access_token: String,
+    ) -> Result<FcmResponse, FcmClientError> {
+        let request = self
+            .http_client
+            .post(send_url)
+            .headers(self.request_headers(send_request.headers))
+            .bearer_auth(access_token)
+            .header(CONTENT_TYPE, "application/json")
+            .body(send_request.body.to_vec())
+            .build()?;
+        #[cfg(feature = "request-signing")]
+        let request = match &self.request_signer {
+            Some(request_signer) => request_signer.sign(request),
+            None => request,
         };
+
+        let response = self.execute_with_transport_retry(request).await?;
+        self.inspectors.inspect_response(&response);
+        // Malformed Retry-After does not fail sending, the raw value is
+        // kept in the response instead.
+        let raw_retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+        let retry_after = raw_retry_after
+            .as_deref()
+            .and_then(|value| value.parse::<RetryAfter>().ok());
         let http_status_code = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
         // Return if I/O error occurs
-        let response_body = response.bytes().await?;
-        let response_json_object = serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(&response_body)
-            .ok()
-            .unwrap_or_default();
+        let response_body = response.bytes().await.map_err(reqwest_error)?;
+        let response_json_object = parse_response_json(http_status_code, content_type.as_deref(), &response_body);
+
+        let response = FcmResponse::new(http_status_code, response_json_object, retry_after)
+            .with_raw_retry_after(raw_retry_after)
+            .with_dry_run(send_request.dry_run);
+        Ok(match send_request.target {
+            Some(target) => response.with_target(target.clone()),
+            None => response,
+        })
+    }
+
+    async fn execute_with_transport_retry(
+        &self,
+        mut request: reqwest::Request,
+    ) -> Result<reqwest::Response, FcmClientError> {
+        let mut attempts_left = self.transport_retry_attempts;
+        loop {
+            let retry_request = if attempts_left > 0 { request.try_clone() } else { None };
+            self.inspectors.inspect_request(&request);
+            match self.execute(request).await {
+                Err(error) if error.is_connect_or_timeout() => match retry_request {
+                    Some(retry_request) => {
+                        request = retry_request;
+                        attempts_left -= 1;
+                    }
+                    None => return Err(error),
+                },
+                result => return result,
+            }
+        }
+    }
 
-        Ok(FcmResponse::new(http_status_code, response_json_object, retry_after))
+    async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, FcmClientError> {
+        #[cfg(feature = "middleware")]
+        if let Some(middleware_client) = &self.middleware_client {
+            return middleware_client.execute(request).await.map_err(|error| match error {
+                reqwest_middleware::Error::Reqwest(error) => reqwest_error(error),
+                error => FcmClientError::Middleware(error),
+            });
+        }
+
+        self.http_client.execute(request).await.map_err(reqwest_error)
     }
 }
+
+/// Map `error` to [FcmClientError::Timeout] if it is a timeout, so that
+/// callers do not need to inspect the reqwest error.
+fn reqwest_error(error: reqwest::Error) -> FcmClientError {
+    if error.is_timeout() {
+        FcmClientError::Timeout
+    } else {
+        FcmClientError::Reqwest(error)
+    }
+}
+
+/// FCM request which may be sent more than once, for example with
+/// the fallback service account.
+struct SendRequest<'a> {
+    body: &'a [u8],
+    /// Target of the message in `body` if it is known.
+    target: Option<&'a Target>,
+    /// Headers in addition to the default headers of the client.
+    headers: Option<&'a HeaderMap>,
+    /// `validate_only` of the message in `body`.
+    dry_run: bool,
+}
+
+/// Serialize JSON request body, with keys of all objects sorted if
+/// `sort_json_keys` is `true`.
+fn request_body(body: &impl serde::Serialize, sort_json_keys: bool) -> Result<Vec<u8>, FcmClientError> {
+    let body = if sort_json_keys {
+        serde_json::to_vec(&canonicalize(serde_json::to_value(body)?))?
+    } else {
+        serde_json::to_vec(body)?
+    };
+    Ok(body)
+}
+
+/// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages/send>
+fn messages_send_url(api_base_url: &str, project_id: &str) -> String {
+    format!("{}/v1/projects/{}/messages:send", api_base_url, project_id)
+}
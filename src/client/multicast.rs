@@ -0,0 +1,92 @@
+use super::{response::FcmResponse, FcmClientError};
+
+/// Tokens of [crate::FcmClient::send_multicast] results grouped by
+/// outcome, see [partition_multicast_results].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MulticastPartition {
+    /// FCM accepted the message.
+    pub delivered: Vec<String>,
+    /// Sending failed but sending again later might succeed: retryable
+    /// FCM errors, timeouts, HTTP errors and open circuit breaker.
+    pub retryable: Vec<String>,
+    /// Sending failed and sending the same message again will most
+    /// likely fail too, for example because the token is unregistered.
+    pub failed: Vec<String>,
+}
+
+/// Group multicast results by outcome. Tokens keep their order in
+/// `results`.
+pub fn partition_multicast_results(results: &[(String, Result<FcmResponse, FcmClientError>)]) -> MulticastPartition {
+    let mut partition = MulticastPartition::default();
+    for (token, result) in results {
+        let bucket = match result {
+            Ok(response) if response.error().is_none() => &mut partition.delivered,
+            _ if is_retryable(result) => &mut partition.retryable,
+            _ => &mut partition.failed,
+        };
+        bucket.push(token.clone());
+    }
+    partition
+}
+
+/// Returns `true` if sending again later might succeed.
+fn is_retryable(result: &Result<FcmResponse, FcmClientError>) -> bool {
+    match result {
+        Ok(response) => response.error().is_some_and(|error| error.is_retryable()),
+        Err(error) => matches!(
+            error,
+            FcmClientError::Timeout | FcmClientError::Reqwest(_) | FcmClientError::CircuitOpen
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn response(http_status_code: u16, json: serde_json::Value) -> Result<FcmResponse, FcmClientError> {
+        let json = json.as_object().unwrap().clone();
+        Ok(FcmResponse::new(http_status_code, json, None))
+    }
+
+    #[test]
+    fn partition_should_group_tokens_by_outcome() {
+        let results = vec![
+            (
+                "a".to_string(),
+                response(200, json!({ "name": "projects/p/messages/1" })),
+            ),
+            (
+                "b".to_string(),
+                response(503, json!({ "error": { "status": "UNAVAILABLE" } })),
+            ),
+            (
+                "c".to_string(),
+                response(404, json!({ "error": { "status": "UNREGISTERED" } })),
+            ),
+            ("d".to_string(), Err(FcmClientError::Timeout)),
+            (
+                "e".to_string(),
+                response(400, json!({ "error": { "status": "INVALID_ARGUMENT" } })),
+            ),
+            ("f".to_string(), Err(FcmClientError::CircuitOpen)),
+            (
+                "g".to_string(),
+                response(200, json!({ "name": "projects/p/messages/2" })),
+            ),
+        ];
+
+        let partition = partition_multicast_results(&results);
+
+        assert_eq!(
+            MulticastPartition {
+                delivered: vec!["a".to_string(), "g".to_string()],
+                retryable: vec!["b".to_string(), "d".to_string(), "f".to_string()],
+                failed: vec!["c".to_string(), "e".to_string()],
+            },
+            partition
+        );
+    }
+}
@@ -1,4 +1,13 @@
 use std::path::PathBuf;
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::RwLock;
+
+use super::clock::{Clock, SystemClock};
+use super::token_store::{TokenStore, TokenStoreAdapter};
 
 use yup_oauth2::authenticator::{Authenticator, DefaultHyperClient, HyperClientBuilder};
 use yup_oauth2::hyper::client::HttpConnector;
@@ -19,6 +28,8 @@ pub enum OauthError {
     AuthenticatorCreatingFailed(std::io::Error),
     #[error("Service account key JSON does not contain project ID")]
     ProjectIdIsMissing,
+    #[error("Service account private key is not valid: {0}")]
+    PrivateKeyInvalid(std::io::Error),
 }
 
 impl OauthError {
@@ -33,51 +44,258 @@ impl OauthError {
     }
 }
 
+/// Returns `true` if `error` from building a yup-oauth2 service account
+/// authenticator is about the private key, which is decoded and turned
+/// into an RSA signer before the token storage is created. Other errors
+/// are from the token storage.
+fn is_private_key_error(error: &std::io::Error) -> bool {
+    let message = error.to_string();
+    match error.kind() {
+        // PEM decoding of the key
+        std::io::ErrorKind::InvalidInput => message.contains("PEM"),
+        // Signer creation from the decoded key
+        std::io::ErrorKind::Other => message.contains("signer") || message.contains("signing scheme"),
+        _ => false,
+    }
+}
+
+/// Where the service account key of [crate::FcmClient] is from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialsSource {
+    /// File path from the `GOOGLE_APPLICATION_CREDENTIALS` environment
+    /// variable.
+    EnvPath,
+    /// File path from [crate::FcmClientBuilder::service_account_key_json_path].
+    ExplicitPath,
+    /// JSON from [crate::FcmClientBuilder::service_account_key_json_string].
+    JsonString,
+    /// JSON from the `GOOGLE_APPLICATION_CREDENTIALS` environment variable.
+    EnvJson,
+}
+
+/// How [crate::FcmClient] gets the access token for FCM requests.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TokenStrategy {
+    /// Get the token from the authenticator for every request. The
+    /// authenticator caches tokens itself, so this does not mean a token
+    /// request for every message.
+    #[default]
+    PerRequest,
+    /// Keep the token in the client and reuse it until it expires in
+    /// less than `refresh_margin`.
+    Cached { refresh_margin: Duration },
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: Option<SystemTime>,
+}
+
+impl CachedToken {
+    /// Tokens without expiration time are never reused.
+    fn is_valid_for(&self, refresh_margin: Duration, now: SystemTime) -> bool {
+        self.expires_at
+            .and_then(|expires_at| expires_at.checked_sub(refresh_margin))
+            .is_some_and(|refresh_at| now < refresh_at)
+    }
+}
+
 pub(crate) struct OauthClient {
-    authenticator: Authenticator<HttpsConnector<HttpConnector>>,
+    authenticator: OauthAuthenticator,
     project_id: String,
+    token_strategy: TokenStrategy,
+    cached_token: RwLock<Option<CachedToken>>,
+    clock: Arc<dyn Clock>,
+}
+
+enum OauthAuthenticator {
+    ServiceAccount(Authenticator<HttpsConnector<HttpConnector>>),
+    /// Returns the next token when refresh is forced, and keeps
+    /// returning the last token after that.
+    /// Tokens expire in an hour.
+    #[cfg(test)]
+    Static {
+        tokens: Vec<String>,
+        current: AtomicUsize,
+        fetch_count: AtomicUsize,
+    },
 }
 
 impl OauthClient {
     pub async fn create_with_key_file(
         service_account_key_path: PathBuf,
         token_cache_json_path: Option<PathBuf>,
+        token_store: Option<Arc<dyn TokenStore>>,
     ) -> Result<Self, OauthError> {
         let file = tokio::fs::read_to_string(&service_account_key_path)
             .await
             .map_err(OauthError::ServiceAccountKeyReadingFailed)?;
-        Self::create_with_string_key(file, token_cache_json_path).await
+        Self::create_with_string_key(file, token_cache_json_path, token_store).await
     }
 
     pub async fn create_with_string_key(
         service_account_key_json_string: String,
         token_cache_json_path: Option<PathBuf>,
+        token_store: Option<Arc<dyn TokenStore>>,
     ) -> Result<Self, OauthError> {
         let key = yup_oauth2::parse_service_account_key(service_account_key_json_string)
             .map_err(OauthError::ServiceAccountKeyReadingFailed)?;
         let oauth_client = DefaultHyperClient.build_hyper_client().map_err(OauthError::Oauth)?;
         let builder = ServiceAccountAuthenticator::with_client(key.clone(), oauth_client);
-        let builder = if let Some(path) = token_cache_json_path {
+        let builder = if let Some(token_store) = token_store {
+            builder.with_storage(Box::new(TokenStoreAdapter(token_store)))
+        } else if let Some(path) = token_cache_json_path {
             builder.persist_tokens_to_disk(path)
         } else {
             builder
         };
-        let authenticator = builder.build().await.map_err(OauthError::AuthenticatorCreatingFailed)?;
+        let authenticator = builder.build().await.map_err(|error| {
+            if is_private_key_error(&error) {
+                OauthError::PrivateKeyInvalid(error)
+            } else {
+                OauthError::AuthenticatorCreatingFailed(error)
+            }
+        })?;
 
         let project_id = key.project_id.ok_or(OauthError::ProjectIdIsMissing)?;
 
         Ok(OauthClient {
-            authenticator,
+            authenticator: OauthAuthenticator::ServiceAccount(authenticator),
             project_id,
+            token_strategy: TokenStrategy::default(),
+            cached_token: RwLock::new(None),
+            clock: Arc::new(SystemClock),
         })
     }
 
+    #[cfg(test)]
+    pub fn create_with_static_tokens(access_tokens: Vec<String>, project_id: impl Into<String>) -> Self {
+        assert!(!access_tokens.is_empty());
+        OauthClient {
+            authenticator: OauthAuthenticator::Static {
+                tokens: access_tokens,
+                current: AtomicUsize::new(0),
+                fetch_count: AtomicUsize::new(0),
+            },
+            project_id: project_id.into(),
+            token_strategy: TokenStrategy::default(),
+            cached_token: RwLock::new(None),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// How many times a token was requested from the static authenticator.
+    #[cfg(test)]
+    pub fn static_token_fetch_count(&self) -> usize {
+        match &self.authenticator {
+            OauthAuthenticator::Static { fetch_count, .. } => fetch_count.load(Ordering::SeqCst),
+            OauthAuthenticator::ServiceAccount(_) => 0,
+        }
+    }
+
+    pub fn token_strategy(&self) -> TokenStrategy {
+        self.token_strategy
+    }
+
+    pub fn set_token_strategy(&mut self, token_strategy: TokenStrategy) {
+        self.token_strategy = token_strategy;
+    }
+
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
     pub async fn get_access_token(&self) -> Result<String, OauthError> {
-        let scopes = [FIREBASE_OAUTH_SCOPE];
-        let access_token = self.authenticator.token(&scopes).await?;
-        let access_token = access_token.token().ok_or(OauthError::AccessTokenIsMissing)?;
+        let refresh_margin = match self.token_strategy {
+            TokenStrategy::PerRequest => return Ok(self.fetch_access_token(false).await?.access_token),
+            TokenStrategy::Cached { refresh_margin } => refresh_margin,
+        };
+
+        if let Some(cached) = self.cached_token.read().await.as_ref() {
+            if cached.is_valid_for(refresh_margin, self.now()) {
+                return Ok(cached.access_token.clone());
+            }
+        }
+
+        let mut cached_token = self.cached_token.write().await;
+        // Other task may have refreshed the token while waiting for the lock.
+        if let Some(cached) = cached_token.as_ref() {
+            if cached.is_valid_for(refresh_margin, self.now()) {
+                return Ok(cached.access_token.clone());
+            }
+        }
+        let token = self.fetch_access_token(false).await?;
+        let access_token = token.access_token.clone();
+        *cached_token = Some(token);
+        Ok(access_token)
+    }
+
+    pub async fn get_access_token_for_scopes(&self, scopes: &[&str]) -> Result<String, OauthError> {
+        match &self.authenticator {
+            OauthAuthenticator::ServiceAccount(authenticator) => {
+                let access_token = authenticator.token(scopes).await?;
+                let access_token = access_token.token().ok_or(OauthError::AccessTokenIsMissing)?;
+
+                Ok(access_token.to_string())
+            }
+            #[cfg(test)]
+            OauthAuthenticator::Static { tokens, current, .. } => Ok(tokens[current.load(Ordering::SeqCst)].clone()),
+        }
+    }
+
+    /// Get new access token even if the cached token is not expired.
+    pub async fn force_refresh_access_token(&self) -> Result<String, OauthError> {
+        let token = self.fetch_access_token(true).await?;
+        let access_token = token.access_token.clone();
+        if let TokenStrategy::Cached { .. } = self.token_strategy {
+            *self.cached_token.write().await = Some(token);
+        }
+        Ok(access_token)
+    }
+
+    fn now(&self) -> SystemTime {
+        self.clock.now().into()
+    }
+
+    /// Get access token with the Firebase scope from the authenticator.
+    async fn fetch_access_token(&self, force_refresh: bool) -> Result<CachedToken, OauthError> {
+        match &self.authenticator {
+            OauthAuthenticator::ServiceAccount(authenticator) => {
+                let access_token = if force_refresh {
+                    authenticator.force_refreshed_token(&[FIREBASE_OAUTH_SCOPE]).await?
+                } else {
+                    authenticator.token(&[FIREBASE_OAUTH_SCOPE]).await?
+                };
+                let expires_at = access_token.expiration_time().map(|expiration_time| {
+                    UNIX_EPOCH + Duration::from_secs(expiration_time.unix_timestamp().max(0) as u64)
+                });
+                let access_token = access_token.token().ok_or(OauthError::AccessTokenIsMissing)?;
 
-        Ok(access_token.to_string())
+                Ok(CachedToken {
+                    access_token: access_token.to_string(),
+                    expires_at,
+                })
+            }
+            #[cfg(test)]
+            OauthAuthenticator::Static {
+                tokens,
+                current,
+                fetch_count,
+            } => {
+                fetch_count.fetch_add(1, Ordering::SeqCst);
+                let index = if force_refresh {
+                    let next = (current.load(Ordering::SeqCst) + 1).min(tokens.len() - 1);
+                    current.store(next, Ordering::SeqCst);
+                    next
+                } else {
+                    current.load(Ordering::SeqCst)
+                };
+                Ok(CachedToken {
+                    access_token: tokens[index].clone(),
+                    expires_at: Some(self.now() + Duration::from_secs(3600)),
+                })
+            }
+        }
     }
 
     pub fn get_project_id(&self) -> &str {
@@ -0,0 +1,37 @@
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::Context;
+use reqwest::header::{HeaderMap, HeaderValue};
+
+/// W3C Trace Context header of the parent span.
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// W3C Trace Context header of vendor specific trace data.
+const TRACESTATE_HEADER: &str = "tracestate";
+
+/// Add `traceparent` (and `tracestate` if it is not empty) headers of
+/// the span of the current OpenTelemetry context. Nothing is added if
+/// there is no active span.
+pub(crate) fn inject_trace_context(headers: &mut HeaderMap) {
+    let context = Context::current();
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return;
+    }
+    let traceparent = format!(
+        "00-{:032x}-{:016x}-{:02x}",
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags().to_u8()
+    );
+    headers.insert(
+        TRACEPARENT_HEADER,
+        HeaderValue::from_str(&traceparent).expect("Hex is valid header value"),
+    );
+    let tracestate = span_context.trace_state().header();
+    if let Ok(tracestate) = HeaderValue::from_str(&tracestate) {
+        if !tracestate.is_empty() {
+            headers.insert(TRACESTATE_HEADER, tracestate);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+/// How [crate::FcmClient::send_with_policy] sends a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendPolicy {
+    /// Timeout of a single attempt. `None` means no timeout other than
+    /// `fcm_request_timeout` of the client.
+    pub timeout: Option<Duration>,
+    /// Maximum amount of attempts including the first one. `0` is the
+    /// same as `1`.
+    pub max_attempts: u32,
+    /// Wait time between attempts when FCM does not set `Retry-After`.
+    pub backoff: BackoffPolicy,
+}
+
+impl Default for SendPolicy {
+    /// No timeout and a single attempt.
+    fn default() -> Self {
+        SendPolicy {
+            timeout: None,
+            max_attempts: 1,
+            backoff: BackoffPolicy::Exponential {
+                initial: Duration::from_secs(1),
+                max: Duration::from_secs(60),
+            },
+        }
+    }
+}
+
+/// Wait time between send attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackoffPolicy {
+    /// Same wait time before every retry.
+    Constant(Duration),
+    /// Wait time is `initial` before the first retry and doubles for
+    /// every retry after that, but is at most `max`.
+    Exponential { initial: Duration, max: Duration },
+}
+
+impl BackoffPolicy {
+    /// Wait time before retry number `retry` (starting from `0`).
+    pub fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            BackoffPolicy::Constant(delay) => delay,
+            BackoffPolicy::Exponential { initial, max } => initial
+                .checked_mul(2u32.saturating_pow(retry))
+                .map_or(max, |delay| delay.min(max)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_backoff_should_double_until_max() {
+        let backoff = BackoffPolicy::Exponential {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(10),
+        };
+
+        let delays: Vec<u64> = (0..5).map(|retry| backoff.delay(retry).as_secs()).collect();
+
+        assert_eq!(vec![1, 2, 4, 8, 10], delays);
+        assert_eq!(Duration::from_secs(10), backoff.delay(u32::MAX));
+    }
+}
@@ -1,48 +1,131 @@
 use chrono::{DateTime, FixedOffset};
 
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::time::Duration;
 use std::{
     convert::{TryFrom, TryInto},
     str::FromStr,
 };
 
-/// Error cases which can be detected from [FcmResponse].
-///
-/// Check <https://firebase.google.com/docs/reference/fcm/rest/v1/ErrorCode>
-/// for more information.
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum FcmResponseError {
+use super::{Clock, SystemClock};
+use crate::message::Target;
+
+/// Define [FcmResponseError] from a table of
+/// `Variant = "ERROR_CODE" | "ALIAS"..., http: CODE?, retryable: BOOL;`
+/// rows, so that the mappings between variants, HTTP status codes and
+/// status strings are generated from the same place. The first status
+/// string is the FCM `ErrorCode` name and the rest are matching
+/// `google.rpc.Code` names.
+macro_rules! fcm_response_errors {
+    ($(
+        $(#[$meta:meta])*
+        $variant:ident = $status:literal $(| $alias:literal)*, http: $($http_status_code:literal)?, retryable: $retryable:literal;
+    )*) => {
+        /// Error cases which can be detected from [FcmResponse].
+        ///
+        /// Check <https://firebase.google.com/docs/reference/fcm/rest/v1/ErrorCode>
+        /// for more information.
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub enum FcmResponseError {
+            $($(#[$meta])* $variant,)*
+        }
+
+        impl FcmResponseError {
+            /// Map FCM `ErrorCode` or the matching `google.rpc.Code` name
+            /// (which is the `error.status` value of the response) to error.
+            pub fn from_status_string(status: &str) -> Option<Self> {
+                match status {
+                    $($status $(| $alias)* => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// FCM `ErrorCode` name of the error.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $status,)*
+                }
+            }
+
+            /// If this is `true` then sending the message again later might
+            /// succeed.
+            pub fn is_retryable(&self) -> bool {
+                match self {
+                    $(Self::$variant => $retryable,)*
+                }
+            }
+        }
+
+        impl TryFrom<u16> for FcmResponseError {
+            type Error = ();
+
+            fn try_from(value: u16) -> Result<Self, Self::Error> {
+                match value {
+                    $($($http_status_code => Ok(Self::$variant),)?)*
+                    _ => Err(()),
+                }
+            }
+        }
+
+        /// Rows of the table `FcmResponseError` is generated from, as
+        /// `(error, HTTP status code, status strings, retryable)`.
+        #[cfg(test)]
+        const FCM_RESPONSE_ERROR_TABLE: &[(FcmResponseError, Option<u16>, &[&str], bool)] = &[
+            $((
+                FcmResponseError::$variant,
+                fcm_response_errors!(@option $($http_status_code)?),
+                &[$status $(, $alias)*],
+                $retryable,
+            ),)*
+        ];
+    };
+    (@option $value:literal) => { Some($value) };
+    (@option) => { None };
+}
+
+fcm_response_errors! {
     /// HTTP 400
-    InvalidArgument,
+    InvalidArgument = "INVALID_ARGUMENT", http: 400, retryable: false;
     /// HTTP 404
-    Unregistered,
+    Unregistered = "UNREGISTERED" | "NOT_FOUND", http: 404, retryable: false;
     /// HTTP 403
-    SenderIdMismatch,
+    SenderIdMismatch = "SENDER_ID_MISMATCH" | "PERMISSION_DENIED", http: 403, retryable: false;
     /// HTTP 429
-    QuotaExceeded,
+    QuotaExceeded = "QUOTA_EXCEEDED" | "RESOURCE_EXHAUSTED", http: 429, retryable: true;
     /// HTTP 503
-    Unavailable,
+    Unavailable = "UNAVAILABLE", http: 503, retryable: true;
     /// HTTP 500
-    Internal,
+    Internal = "INTERNAL", http: 500, retryable: true;
     /// HTTP 401
-    ThirdPartyAuth,
+    ThirdPartyAuth = "THIRD_PARTY_AUTH_ERROR" | "UNAUTHENTICATED", http: 401, retryable: false;
     /// `UNSPECIFIED_ERROR` (no HTTP error code defined)
-    Unspecified,
+    Unspecified = "UNSPECIFIED_ERROR", http:, retryable: false;
     /// Response is not successful and API reference does not have
     /// matching error.
-    Unknown,
+    Unknown = "UNKNOWN", http:, retryable: false;
+}
+
+impl fmt::Display for FcmResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl FcmResponseError {
+    /// Error is detected primarily from the response JSON, so that an
+    /// error body is reported even if HTTP status code is 200. If the
+    /// JSON does not contain a known error then the HTTP status code
+    /// is used.
     pub fn detect_from(
         http_status_code: u16,
         response_json: &serde_json::Map<String, serde_json::Value>,
     ) -> Option<Self> {
-        if let Ok(error) = http_status_code.try_into() {
+        if let Some(error) = Self::get_error(response_json).and_then(Self::from_status_string) {
+            Some(error)
+        } else if let Ok(error) = http_status_code.try_into() {
             Some(error)
-        } else if Self::get_error(response_json) == Some("UNSPECIFIED_ERROR") {
-            Some(Self::Unspecified)
         } else if response_json.get("name").is_none() {
             Some(Self::Unknown)
         } else {
@@ -52,9 +135,21 @@ impl FcmResponseError {
 
     fn get_error(response_json: &serde_json::Map<String, serde_json::Value>) -> Option<&str> {
         Self::get_error_using_api_reference(response_json)
+            .or_else(|| Self::get_error_using_error_details(response_json))
             .or_else(|| Self::get_error_using_real_response(response_json))
     }
 
+    /// `errorCode` of the `FcmError` detail is more specific than
+    /// `error.status`, which is a `google.rpc.Code`.
+    fn get_error_using_error_details(response_json: &serde_json::Map<String, serde_json::Value>) -> Option<&str> {
+        let details = response_json
+            .get("error")
+            .and_then(|v| v.get("details"))
+            .and_then(|v| v.as_array())?;
+        error_details_of_type(details, FCM_ERROR_TYPE)
+            .find_map(|detail| detail.get("errorCode").and_then(|v| v.as_str()))
+    }
+
     /// Currently (2024-05-26) FCM API response JSON does not have
     /// this location for INVALID_ARGUMENT error.
     fn get_error_using_api_reference(response_json: &serde_json::Map<String, serde_json::Value>) -> Option<&str> {
@@ -72,40 +167,130 @@ impl FcmResponseError {
     }
 }
 
-impl TryFrom<u16> for FcmResponseError {
-    type Error = ();
+/// Max length of the response body text which is stored to the created
+/// error object.
+const NON_JSON_ERROR_MESSAGE_MAX_CHARS: usize = 1024;
 
-    fn try_from(value: u16) -> Result<Self, Self::Error> {
-        match value {
-            400 => Ok(Self::InvalidArgument),
-            404 => Ok(Self::Unregistered),
-            403 => Ok(Self::SenderIdMismatch),
-            429 => Ok(Self::QuotaExceeded),
-            503 => Ok(Self::Unavailable),
-            500 => Ok(Self::Internal),
-            401 => Ok(Self::ThirdPartyAuth),
-            _ => Err(()),
+/// Parse response body to JSON object.
+///
+/// Error responses from something else than FCM API (for example HTML
+/// page from a proxy or load balancer) do not have JSON body. For those
+/// responses an error object in the same format as FCM API uses is
+/// created, so that the response body is not lost. The object has no
+/// `status`, so [FcmResponse::error] is detected from the HTTP status
+/// code and for example a 503 page from a proxy is retryable.
+pub(crate) fn parse_response_json(
+    http_status_code: u16,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> serde_json::Map<String, serde_json::Value> {
+    let is_json = content_type.is_none_or(is_json_content_type);
+    if is_json {
+        if let Ok(response_json_object) = serde_json::from_slice(body) {
+            return response_json_object;
         }
     }
+
+    if (200..300).contains(&http_status_code) {
+        return serde_json::Map::new();
+    }
+
+    let message: String = String::from_utf8_lossy(body)
+        .trim()
+        .chars()
+        .take(NON_JSON_ERROR_MESSAGE_MAX_CHARS)
+        .collect();
+    let error = serde_json::json!({
+        "code": http_status_code,
+        "message": message,
+    });
+    let mut response_json_object = serde_json::Map::new();
+    response_json_object.insert("error".to_string(), error);
+    response_json_object
+}
+
+fn is_json_content_type(content_type: &str) -> bool {
+    let mime_type = content_type.split(';').next().unwrap_or_default().trim();
+    mime_type.eq_ignore_ascii_case("application/json") || mime_type.to_ascii_lowercase().ends_with("+json")
 }
 
 /// HTTP `Retry-After` header value.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Serialized as `{"delay": <seconds>}` or `{"date_time": "<RFC 3339>"}`,
+/// for example to store the retry schedule of a message to a job queue.
+/// Fractions of a second of the delay are not serialized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RetryAfter {
     /// Amount of time to wait until retrying the message is allowed.
+    #[serde(with = "duration_secs")]
     Delay(Duration),
 
     /// A point in time until retrying the message is allowed.
+    #[serde(with = "rfc3339")]
     DateTime(DateTime<FixedOffset>),
 }
 
+mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+mod rfc3339 {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(date_time: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&date_time.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&value).map_err(serde::de::Error::custom)
+    }
+}
+
 impl RetryAfter {
     /// Wait time calculated from current operating system time.
     pub fn wait_time(&self) -> Duration {
-        self.wait_time_with_time_provider(|| Utc::now().fixed_offset())
+        self.wait_time_with_clock(&SystemClock)
+    }
+
+    /// Wait time calculated from the current time of `clock`.
+    pub fn wait_time_with_clock(&self, clock: &dyn Clock) -> Duration {
+        self.wait_time_with_time_provider(|| clock.now().fixed_offset())
+    }
+
+    /// Same as [RetryAfter::wait_time] but limited to range from `min` to
+    /// `max`. If the value is a date, skew of the local clock can make the
+    /// wait time absurdly long or zero, so retry loops should prefer this.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is greater than `max`.
+    pub fn wait_time_clamped(&self, min: Duration, max: Duration) -> Duration {
+        self.wait_time_clamped_with_clock(min, max, &SystemClock)
+    }
+
+    /// Same as [RetryAfter::wait_time_clamped] but the current time is
+    /// from `clock`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is greater than `max`.
+    pub fn wait_time_clamped_with_clock(&self, min: Duration, max: Duration, clock: &dyn Clock) -> Duration {
+        self.wait_time_with_clock(clock).clamp(min, max)
     }
 
-    fn wait_time_with_time_provider(&self, get_time: impl FnOnce() -> DateTime<FixedOffset>) -> Duration {
+    pub(crate) fn wait_time_with_time_provider(&self, get_time: impl FnOnce() -> DateTime<FixedOffset>) -> Duration {
         match *self {
             RetryAfter::Delay(duration) => duration,
             RetryAfter::DateTime(date_time) => (date_time - get_time())
@@ -128,11 +313,16 @@ impl FromStr for RetryAfter {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FcmResponse {
     http_status_code: u16,
     response_json_object: serde_json::Map<String, serde_json::Value>,
     retry_after: Option<RetryAfter>,
+    /// `Retry-After` header value as is.
+    raw_retry_after: Option<String>,
+    dry_run: bool,
+    /// Target of the sent message.
+    target: Option<Target>,
 }
 
 impl FcmResponse {
@@ -145,12 +335,34 @@ impl FcmResponse {
             http_status_code,
             response_json_object,
             retry_after,
+            raw_retry_after: None,
+            dry_run: false,
+            target: None,
         }
     }
 
+    pub(crate) fn with_target(mut self, target: Target) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    pub(crate) fn with_raw_retry_after(mut self, raw_retry_after: Option<String>) -> Self {
+        self.raw_retry_after = raw_retry_after;
+        self
+    }
+
+    pub(crate) fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
     /// If `None` then [crate::message::Message] is sent successfully.
+    ///
+    /// The action depends on the target of the sent message, as
+    /// [FcmResponseError::Unregistered] means a removed app only for
+    /// token targets.
     pub fn recommended_error_handling_action(&self) -> Option<RecomendedAction> {
-        RecomendedAction::analyze(self)
+        RecomendedAction::analyze(self, self.target.as_ref())
     }
 
     /// If `None` then [crate::message::Message] is sent successfully.
@@ -166,9 +378,254 @@ impl FcmResponse {
         &self.response_json_object
     }
 
+    /// Parsed `Retry-After` header. `None` also if the header value is
+    /// not valid, see [FcmResponse::raw_retry_after].
     pub fn retry_after(&self) -> Option<&RetryAfter> {
         self.retry_after.as_ref()
     }
+
+    /// `Retry-After` header value as is, also if it could not be parsed
+    /// to [RetryAfter]. Non UTF-8 bytes are replaced with `U+FFFD`.
+    pub fn raw_retry_after(&self) -> Option<&str> {
+        self.raw_retry_after.as_deref()
+    }
+
+    /// Take HTTP status code, response JSON and `Retry-After` value
+    /// without cloning them.
+    pub fn into_parts(self) -> (u16, serde_json::Map<String, serde_json::Value>, Option<RetryAfter>) {
+        (self.http_status_code, self.response_json_object, self.retry_after)
+    }
+
+    /// Message ID (the `name` field of the response), for example
+    /// `projects/myproject/messages/0:1500415314455276%31bd1c9631bd1c96`.
+    ///
+    /// If [FcmResponse::was_dry_run] is `true`, FCM only validated the
+    /// message and the ID is synthetic: it looks like a real ID but no
+    /// message was delivered.
+    pub fn message_id(&self) -> Option<&str> {
+        self.response_json_object.get("name").and_then(|name| name.as_str())
+    }
+
+    /// `true` if the message was sent with `validate_only` (see
+    /// [crate::FcmClientBuilder::dry_run]), so the message was not
+    /// delivered even if sending succeeded.
+    pub fn was_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Human readable one line summary of the response for logging,
+    /// for example `FCM error QUOTA_EXCEEDED (429); retry after 30s`.
+    pub fn describe(&self) -> String {
+        let mut description = match self.error() {
+            Some(error) => format!("FCM error {} ({})", error.as_str(), self.http_status_code),
+            None => format!("FCM message sent ({})", self.http_status_code),
+        };
+        match &self.retry_after {
+            Some(RetryAfter::Delay(delay)) => {
+                description.push_str(&format!("; retry after {}s", delay.as_secs()));
+            }
+            Some(RetryAfter::DateTime(date_time)) => {
+                description.push_str(&format!("; retry after {}", date_time.to_rfc2822()));
+            }
+            None => (),
+        }
+        description
+    }
+
+    /// Returns `true` if the error is caused by configuration of the
+    /// Firebase project or credentials, so that sending again does not
+    /// help: [FcmResponseError::SenderIdMismatch] or
+    /// [FcmResponseError::ThirdPartyAuth]. Useful for alerting, as the
+    /// other errors are either transient or specific to the message.
+    pub fn indicates_misconfiguration(&self) -> bool {
+        matches!(
+            self.error(),
+            Some(FcmResponseError::SenderIdMismatch | FcmResponseError::ThirdPartyAuth)
+        )
+    }
+
+    /// Entries of the `error.details` array. Empty if the response does
+    /// not have error details.
+    pub fn error_details(&self) -> &[serde_json::Value] {
+        self.response_json_object
+            .get("error")
+            .and_then(|error| error.get("details"))
+            .and_then(|details| details.as_array())
+            .map_or(&[], |details| details.as_slice())
+    }
+
+    /// `errorCode` of the `google.firebase.fcm.v1.FcmError` detail, for
+    /// example `UNREGISTERED` when `error.status` is `NOT_FOUND`.
+    pub fn fcm_error_code(&self) -> Option<&str> {
+        FcmResponseError::get_error_using_error_details(&self.response_json_object)
+    }
+
+    /// Entries of the `error.details` array parsed by their `@type`.
+    pub fn details(&self) -> Vec<ErrorDetail> {
+        self.error_details().iter().map(ErrorDetail::parse).collect()
+    }
+
+    /// Field violations of every `google.rpc.BadRequest` detail, which
+    /// tell which message fields are invalid.
+    pub fn field_violations(&self) -> Vec<FieldViolation> {
+        self.details()
+            .into_iter()
+            .flat_map(|detail| match detail {
+                ErrorDetail::BadRequest { field_violations } => field_violations,
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    /// `subject` of the first `google.rpc.QuotaFailure` violation, which
+    /// tells which quota was exceeded when the error is
+    /// [FcmResponseError::QuotaExceeded].
+    pub fn quota_kind(&self) -> Option<String> {
+        self.details()
+            .into_iter()
+            .flat_map(|detail| match detail {
+                ErrorDetail::QuotaFailure { violations } => violations,
+                _ => Vec::new(),
+            })
+            .map(|violation| violation.subject)
+            .next()
+    }
+}
+
+/// Entry of the `error.details` array of an error response, see
+/// [FcmResponse::details]. The variant is selected by the `@type` of the
+/// entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorDetail {
+    /// `google.firebase.fcm.v1.FcmError`
+    FcmError {
+        /// FCM `ErrorCode` name, see [FcmResponseError::from_status_string].
+        error_code: String,
+    },
+    /// `google.rpc.BadRequest`
+    BadRequest { field_violations: Vec<FieldViolation> },
+    /// `google.rpc.QuotaFailure`
+    QuotaFailure { violations: Vec<QuotaViolation> },
+    /// `google.rpc.Help`
+    Help { links: Vec<HelpLink> },
+    /// Entry which has other `@type`, or which does not match the format
+    /// of its `@type`.
+    Unknown(serde_json::Value),
+}
+
+impl ErrorDetail {
+    fn parse(detail: &serde_json::Value) -> Self {
+        let detail_type = detail
+            .get("@type")
+            .and_then(|v| v.as_str())
+            .and_then(|detail_type| detail_type.rsplit('/').next());
+        let parsed = match detail_type {
+            Some(FCM_ERROR_TYPE) => {
+                serde_json::from_value(detail.clone()).map(|detail: FcmErrorDetail| ErrorDetail::FcmError {
+                    error_code: detail.error_code,
+                })
+            }
+            Some(BAD_REQUEST_TYPE) => {
+                serde_json::from_value(detail.clone()).map(|detail: BadRequestDetail| ErrorDetail::BadRequest {
+                    field_violations: detail.field_violations,
+                })
+            }
+            Some(QUOTA_FAILURE_TYPE) => {
+                serde_json::from_value(detail.clone()).map(|detail: QuotaFailureDetail| ErrorDetail::QuotaFailure {
+                    violations: detail.violations,
+                })
+            }
+            Some(HELP_TYPE) => serde_json::from_value(detail.clone())
+                .map(|detail: HelpDetail| ErrorDetail::Help { links: detail.links }),
+            _ => return ErrorDetail::Unknown(detail.clone()),
+        };
+        parsed.unwrap_or_else(|_| ErrorDetail::Unknown(detail.clone()))
+    }
+}
+
+/// Invalid message field from a `google.rpc.BadRequest` error detail,
+/// see [FcmResponse::field_violations].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FieldViolation {
+    /// Path of the invalid field, for example `message.token`.
+    #[serde(default)]
+    pub field: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Exceeded quota from a `google.rpc.QuotaFailure` error detail.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct QuotaViolation {
+    /// What the quota is for, see [FcmResponse::quota_kind].
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Link to documentation from a `google.rpc.Help` error detail.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct HelpLink {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FcmErrorDetail {
+    error_code: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BadRequestDetail {
+    #[serde(default)]
+    field_violations: Vec<FieldViolation>,
+}
+
+#[derive(Deserialize)]
+struct QuotaFailureDetail {
+    #[serde(default)]
+    violations: Vec<QuotaViolation>,
+}
+
+#[derive(Deserialize)]
+struct HelpDetail {
+    #[serde(default)]
+    links: Vec<HelpLink>,
+}
+
+/// Result of [crate::FcmClient::validate].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationResult {
+    /// FCM accepted the message.
+    Valid,
+    /// FCM rejected the message. The field violations can be empty if
+    /// the response did not tell which fields are invalid.
+    Invalid(Vec<FieldViolation>),
+}
+
+const FCM_ERROR_TYPE: &str = "google.firebase.fcm.v1.FcmError";
+const BAD_REQUEST_TYPE: &str = "google.rpc.BadRequest";
+const QUOTA_FAILURE_TYPE: &str = "google.rpc.QuotaFailure";
+const HELP_TYPE: &str = "google.rpc.Help";
+
+/// Error details which have `@type` `type.googleapis.com/<type_name>`.
+/// The details array can contain any number of entries in any order.
+fn error_details_of_type<'a>(
+    details: &'a [serde_json::Value],
+    type_name: &'a str,
+) -> impl Iterator<Item = &'a serde_json::Value> {
+    details.iter().filter(move |detail| {
+        detail
+            .get("@type")
+            .and_then(|v| v.as_str())
+            .and_then(|detail_type| detail_type.rsplit('/').next())
+            == Some(type_name)
+    })
 }
 
 /// Error handling action which server or developer should do based on
@@ -184,6 +641,11 @@ pub enum RecomendedAction<'a> {
     /// missing or unregistered and should be removed.
     RemoveFcmAppToken,
 
+    /// Error [FcmResponseError::Unregistered] was detected for a topic
+    /// or condition target. No token should be removed; check that the
+    /// topic exists and the target is correct.
+    TopicNotFound,
+
     /// Error [FcmResponseError::InvalidArgument] was detected. Check
     /// that the sent message is correct.
     FixMessageContent,
@@ -219,10 +681,40 @@ pub enum RecomendedAction<'a> {
 }
 
 impl RecomendedAction<'_> {
-    fn analyze(response: &FcmResponse) -> Option<RecomendedAction> {
+    /// Wait time before retry number `attempt` (starting from `0`) for
+    /// [RecomendedAction::ReduceMessageRateAndRetry] and
+    /// [RecomendedAction::Retry], see [RecomendedWaitTime::next_backoff]
+    /// (without jitter).
+    ///
+    /// Returns `None` if sending the message again does not help.
+    pub fn next_wait(&self, attempt: u32) -> Option<Duration> {
+        self.next_wait_with_clock(attempt, &SystemClock)
+    }
+
+    /// Same as [RecomendedAction::next_wait] but wait times of
+    /// `Retry-After` dates are calculated from the current time of
+    /// `clock`.
+    pub fn next_wait_with_clock(&self, attempt: u32, clock: &dyn Clock) -> Option<Duration> {
+        let wait_time = match self {
+            RecomendedAction::ReduceMessageRateAndRetry(wait_time) | RecomendedAction::Retry(wait_time) => wait_time,
+            RecomendedAction::RemoveFcmAppToken
+            | RecomendedAction::TopicNotFound
+            | RecomendedAction::FixMessageContent
+            | RecomendedAction::CheckSenderIdEquality
+            | RecomendedAction::CheckIosAndWebCredentials
+            | RecomendedAction::HandleUnknownError => return None,
+        };
+        Some(wait_time.next_backoff_with_clock(attempt, false, clock))
+    }
+
+    /// If `target` is `None`, it is assumed to be a token.
+    fn analyze<'a>(response: &'a FcmResponse, target: Option<&Target>) -> Option<RecomendedAction<'a>> {
         let action = match response.error()? {
             FcmResponseError::Unspecified | FcmResponseError::Unknown { .. } => RecomendedAction::HandleUnknownError,
-            FcmResponseError::Unregistered => RecomendedAction::RemoveFcmAppToken,
+            FcmResponseError::Unregistered => match target {
+                Some(Target::Topic(_) | Target::Condition(_)) => RecomendedAction::TopicNotFound,
+                Some(Target::Token(_)) | None => RecomendedAction::RemoveFcmAppToken,
+            },
             FcmResponseError::InvalidArgument => RecomendedAction::FixMessageContent,
             FcmResponseError::SenderIdMismatch => RecomendedAction::CheckSenderIdEquality,
             FcmResponseError::QuotaExceeded => {
@@ -252,6 +744,56 @@ impl RecomendedAction<'_> {
     }
 }
 
+/// Result of [crate::FcmClient::send_handled] which tells what the caller
+/// should do next without matching [RecomendedAction].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HandledOutcome {
+    /// `true` if the message was sent successfully.
+    pub ok: bool,
+    /// Token target of the message if it should be removed, because the
+    /// app is no longer registered.
+    pub remove_token: Option<String>,
+    /// Wait this long and then send the message again.
+    pub retry_after: Option<Duration>,
+}
+
+impl HandledOutcome {
+    /// `now` is used for wait times of `Retry-After` dates.
+    pub(crate) fn new(response: &FcmResponse, target: &Target, now: DateTime<Utc>) -> Self {
+        let action = match RecomendedAction::analyze(response, Some(target)) {
+            Some(action) => action,
+            None => {
+                return HandledOutcome {
+                    ok: true,
+                    ..Default::default()
+                }
+            }
+        };
+        let mut outcome = HandledOutcome::default();
+        match action {
+            RecomendedAction::RemoveFcmAppToken => {
+                if let Target::Token(token) = target {
+                    outcome.remove_token = Some(token.clone());
+                }
+            }
+            RecomendedAction::ReduceMessageRateAndRetry(wait_time) | RecomendedAction::Retry(wait_time) => {
+                outcome.retry_after = Some(match wait_time {
+                    RecomendedWaitTime::InitialWaitTime(wait_time) => wait_time,
+                    RecomendedWaitTime::SpecificWaitTime(retry_after) => {
+                        retry_after.wait_time_with_time_provider(|| now.fixed_offset())
+                    }
+                });
+            }
+            RecomendedAction::TopicNotFound
+            | RecomendedAction::FixMessageContent
+            | RecomendedAction::CheckSenderIdEquality
+            | RecomendedAction::CheckIosAndWebCredentials
+            | RecomendedAction::HandleUnknownError => (),
+        }
+        outcome
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum RecomendedWaitTime<'a> {
     /// Initial wait time for exponential back-off.
@@ -262,18 +804,92 @@ pub enum RecomendedWaitTime<'a> {
     /// grows exponentially).
     ///
     /// Note also that Google documentation also recommends implementing
-    /// jittering to exponential back-off.
+    /// jittering to exponential back-off, see
+    /// [RecomendedWaitTime::next_backoff].
     InitialWaitTime(Duration),
 
     /// Specific wait time from HTTP header.
     SpecificWaitTime(&'a RetryAfter),
 }
 
+/// Max wait time of [RecomendedWaitTime::next_backoff] for
+/// [RecomendedWaitTime::InitialWaitTime].
+pub const MAX_BACKOFF_WAIT_TIME: Duration = Duration::from_secs(60 * 60);
+
+impl RecomendedWaitTime<'_> {
+    /// Wait time before retry number `attempt` (starting from `0`).
+    ///
+    /// [RecomendedWaitTime::InitialWaitTime] doubles for every attempt
+    /// and is at most [MAX_BACKOFF_WAIT_TIME]. If `jitter` is `true` the
+    /// wait time is randomized to between half and all of it, so that
+    /// clients which failed at the same time do not retry at the same
+    /// time. [RecomendedWaitTime::SpecificWaitTime] is the wait time
+    /// requested by the server and is never randomized.
+    pub fn next_backoff(&self, attempt: u32, jitter: bool) -> Duration {
+        self.next_backoff_with_clock(attempt, jitter, &SystemClock)
+    }
+
+    /// Same as [RecomendedWaitTime::next_backoff] but wait time of a
+    /// `Retry-After` date is calculated from the current time of `clock`.
+    pub fn next_backoff_with_clock(&self, attempt: u32, jitter: bool, clock: &dyn Clock) -> Duration {
+        let wait_time = match self {
+            RecomendedWaitTime::InitialWaitTime(initial) => initial
+                .checked_mul(2u32.saturating_pow(attempt))
+                .map_or(MAX_BACKOFF_WAIT_TIME, |wait_time| wait_time.min(MAX_BACKOFF_WAIT_TIME)),
+            RecomendedWaitTime::SpecificWaitTime(retry_after) => return retry_after.wait_time_with_clock(clock),
+        };
+        if jitter {
+            let half = wait_time / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            wait_time
+        }
+    }
+}
+
+/// Random number in `[0, 1]`. Randomness is from the randomly seeded
+/// hasher of the standard library, which is good enough for jitter.
+fn random_fraction() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    random as f64 / u64::MAX as f64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::DateTime;
 
+    #[test]
+    fn test_error_table_mappings_agree() {
+        for &(error, http_status_code, status_strings, retryable) in FCM_RESPONSE_ERROR_TABLE {
+            assert_eq!(status_strings[0], error.as_str());
+            assert_eq!(status_strings[0], error.to_string());
+            for status in status_strings {
+                assert_eq!(Some(error), FcmResponseError::from_status_string(status));
+            }
+            if let Some(http_status_code) = http_status_code {
+                assert_eq!(Ok(error), FcmResponseError::try_from(http_status_code));
+            }
+            assert_eq!(retryable, error.is_retryable());
+        }
+    }
+
+    #[test]
+    fn test_error_table_has_unique_mappings() {
+        let mut http_status_codes = std::collections::HashSet::new();
+        let mut status_strings = std::collections::HashSet::new();
+        for &(_, http_status_code, statuses, _) in FCM_RESPONSE_ERROR_TABLE {
+            if let Some(http_status_code) = http_status_code {
+                assert!(http_status_codes.insert(http_status_code));
+            }
+            for status in statuses {
+                assert!(status_strings.insert(*status));
+            }
+        }
+    }
+
     #[test]
     fn test_retry_after_from_seconds() {
         let expected_wait_time = Duration::from_secs(1);
@@ -285,6 +901,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_retry_after_delay_serialization_round_trip() {
+        let retry_after = RetryAfter::Delay(Duration::from_secs(30));
+
+        let json = serde_json::to_value(&retry_after).unwrap();
+
+        assert_eq!(serde_json::json!({ "delay": 30 }), json);
+        assert_eq!(retry_after, serde_json::from_value(json).unwrap());
+    }
+
+    #[test]
+    fn test_retry_after_date_time_serialization_round_trip() {
+        let retry_after = RetryAfter::from_str("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+
+        let json = serde_json::to_value(&retry_after).unwrap();
+
+        assert_eq!(serde_json::json!({ "date_time": "1994-11-06T08:49:37+00:00" }), json);
+        assert_eq!(retry_after, serde_json::from_value(json).unwrap());
+    }
+
+    #[test]
+    fn test_retry_after_wait_time_clamped_to_max() {
+        let retry_after = RetryAfter::DateTime(Utc::now().fixed_offset() + chrono::Duration::days(365));
+
+        let wait_time = retry_after.wait_time_clamped(Duration::from_secs(1), Duration::from_secs(300));
+
+        assert_eq!(Duration::from_secs(300), wait_time);
+    }
+
+    #[test]
+    fn test_retry_after_wait_time_clamped_to_min() {
+        let retry_after = RetryAfter::DateTime(DateTime::parse_from_rfc2822("Sun, 06 Nov 1994 08:49:37 GMT").unwrap());
+
+        let wait_time = retry_after.wait_time_clamped(Duration::from_secs(1), Duration::from_secs(300));
+
+        assert_eq!(Duration::from_secs(1), wait_time);
+    }
+
+    #[test]
+    fn test_retry_after_wait_time_clamped_within_range() {
+        let retry_after = RetryAfter::Delay(Duration::from_secs(30));
+
+        let wait_time = retry_after.wait_time_clamped(Duration::from_secs(1), Duration::from_secs(300));
+
+        assert_eq!(Duration::from_secs(30), wait_time);
+    }
+
     #[test]
     fn test_retry_after_from_date() {
         let date = "Sun, 06 Nov 1994 08:49:37 GMT";
@@ -334,4 +997,387 @@ mod tests {
             retry_after.wait_time_with_time_provider(|| past_date_time),
         );
     }
+
+    #[test]
+    fn test_parse_response_json_from_html_503() {
+        let body = b"<html><body>Service Unavailable</body></html>";
+        let json = parse_response_json(503, Some("text/html; charset=UTF-8"), body);
+        let response = FcmResponse::new(503, json, None);
+
+        assert_eq!(Some(FcmResponseError::Unavailable), response.error());
+        assert!(response.error().unwrap().is_retryable());
+        let error = &response.json()["error"];
+        assert_eq!(503, error["code"]);
+        assert_eq!("<html><body>Service Unavailable</body></html>", error["message"]);
+        assert!(error.get("status").is_none());
+    }
+
+    #[test]
+    fn test_parse_response_json_from_non_json_error_without_fcm_mapping() {
+        let json = parse_response_json(502, Some("text/plain"), b"Bad Gateway");
+        let response = FcmResponse::new(502, json, None);
+
+        assert_eq!(Some(FcmResponseError::Unknown), response.error());
+    }
+
+    #[test]
+    fn test_into_parts() {
+        let json = parse_response_json(
+            429,
+            Some("application/json"),
+            br#"{"error":{"status":"QUOTA_EXCEEDED"}}"#,
+        );
+        let response = FcmResponse::new(429, json.clone(), Some(RetryAfter::Delay(Duration::from_secs(30))));
+
+        let (http_status_code, response_json_object, retry_after) = response.into_parts();
+
+        assert_eq!(429, http_status_code);
+        assert_eq!(json, response_json_object);
+        assert_eq!(Some(RetryAfter::Delay(Duration::from_secs(30))), retry_after);
+    }
+
+    #[test]
+    fn test_unregistered_token_should_be_removed() {
+        let json = parse_response_json(404, Some("application/json"), br#"{"error":{"status":"UNREGISTERED"}}"#);
+        let response = FcmResponse::new(404, json, None).with_target(Target::Token("token".to_string()));
+
+        assert_eq!(
+            Some(RecomendedAction::RemoveFcmAppToken),
+            response.recommended_error_handling_action()
+        );
+    }
+
+    #[test]
+    fn test_unregistered_topic_should_not_remove_token() {
+        let json = parse_response_json(404, Some("application/json"), br#"{"error":{"status":"NOT_FOUND"}}"#);
+        let response = FcmResponse::new(404, json, None).with_target(Target::Topic("news".to_string()));
+
+        assert_eq!(
+            Some(RecomendedAction::TopicNotFound),
+            response.recommended_error_handling_action()
+        );
+        assert_eq!(
+            HandledOutcome::default(),
+            HandledOutcome::new(&response, &Target::Topic("news".to_string()), Utc::now())
+        );
+    }
+
+    #[test]
+    fn test_parse_response_json_from_json_404() {
+        let body = br#"{"error":{"code":404,"status":"NOT_FOUND"}}"#;
+        let json = parse_response_json(404, Some("application/json; charset=UTF-8"), body);
+        let response = FcmResponse::new(404, json, None);
+
+        assert_eq!(Some(FcmResponseError::Unregistered), response.error());
+        assert_eq!("NOT_FOUND", response.json()["error"]["status"]);
+    }
+
+    #[test]
+    fn test_detect_error_from_json_when_http_status_is_ok() {
+        let json = parse_response_json(
+            200,
+            Some("application/json"),
+            br#"{"error":{"code":503,"status":"UNAVAILABLE"}}"#,
+        );
+        let response = FcmResponse::new(200, json, None);
+
+        assert_eq!(Some(FcmResponseError::Unavailable), response.error());
+    }
+
+    #[test]
+    fn test_detect_error_prefers_json_status_over_http_status() {
+        let json = parse_response_json(
+            400,
+            Some("application/json"),
+            br#"{"error":{"code":400,"status":"UNSPECIFIED_ERROR"}}"#,
+        );
+        let response = FcmResponse::new(400, json, None);
+
+        assert_eq!(Some(FcmResponseError::Unspecified), response.error());
+    }
+
+    #[test]
+    fn test_detect_error_from_http_status_when_json_status_is_not_known() {
+        let json = parse_response_json(
+            429,
+            Some("application/json"),
+            br#"{"error":{"code":429,"status":"SOMETHING_NEW"}}"#,
+        );
+        let response = FcmResponse::new(429, json, None);
+
+        assert_eq!(Some(FcmResponseError::QuotaExceeded), response.error());
+    }
+
+    #[test]
+    fn test_no_error_for_ok_response() {
+        let json = parse_response_json(200, Some("application/json"), br#"{"name":"projects/p/messages/1"}"#);
+        let response = FcmResponse::new(200, json, None);
+
+        assert_eq!(None, response.error());
+    }
+
+    #[test]
+    fn test_response_equality() {
+        let response = |message_name: &str| {
+            let mut json = serde_json::Map::new();
+            json.insert("name".to_string(), serde_json::Value::from(message_name));
+            FcmResponse::new(200, json, Some(RetryAfter::Delay(Duration::from_secs(1))))
+        };
+
+        assert_eq!(response("projects/p/messages/1"), response("projects/p/messages/1"));
+        assert_ne!(response("projects/p/messages/1"), response("projects/p/messages/2"));
+    }
+
+    #[test]
+    fn test_describe_retryable_response() {
+        let json = parse_response_json(
+            429,
+            Some("application/json"),
+            br#"{"error":{"code":429,"status":"RESOURCE_EXHAUSTED"}}"#,
+        );
+        let response = FcmResponse::new(429, json, Some(RetryAfter::Delay(Duration::from_secs(30))));
+
+        assert_eq!("FCM error QUOTA_EXCEEDED (429); retry after 30s", response.describe());
+    }
+
+    #[test]
+    fn test_describe_non_retryable_response() {
+        let json = parse_response_json(
+            404,
+            Some("application/json"),
+            br#"{"error":{"code":404,"status":"NOT_FOUND"}}"#,
+        );
+        let response = FcmResponse::new(404, json, None);
+
+        assert_eq!("FCM error UNREGISTERED (404)", response.describe());
+    }
+
+    #[test]
+    fn test_describe_successful_response() {
+        let json = parse_response_json(200, Some("application/json"), br#"{"name":"projects/p/messages/1"}"#);
+        let response = FcmResponse::new(200, json, None);
+
+        assert_eq!("FCM message sent (200)", response.describe());
+    }
+
+    #[test]
+    fn test_parse_response_json_truncates_long_non_json_body() {
+        let body = "x".repeat(NON_JSON_ERROR_MESSAGE_MAX_CHARS + 1);
+        let json = parse_response_json(502, Some("text/plain"), body.as_bytes());
+
+        let message = json["error"]["message"].as_str().unwrap();
+        assert_eq!(NON_JSON_ERROR_MESSAGE_MAX_CHARS, message.len());
+    }
+
+    #[test]
+    fn test_error_details_are_parsed_from_every_array_entry() {
+        let json = serde_json::json!({
+            "error": {
+                "code": 400,
+                "status": "INVALID_ARGUMENT",
+                "details": [
+                    {
+                        "@type": "type.googleapis.com/google.rpc.BadRequest",
+                        "fieldViolations": [
+                            { "field": "message.token", "description": "Invalid registration token" }
+                        ]
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.firebase.fcm.v1.FcmError",
+                        "errorCode": "SENDER_ID_MISMATCH"
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.rpc.BadRequest",
+                        "fieldViolations": [
+                            { "field": "message.data", "description": "Invalid value" }
+                        ]
+                    }
+                ]
+            }
+        });
+        let response = FcmResponse::new(400, json.as_object().unwrap().clone(), None);
+
+        assert_eq!(3, response.error_details().len());
+        assert_eq!(Some("SENDER_ID_MISMATCH"), response.fcm_error_code());
+        assert_eq!(Some(FcmResponseError::SenderIdMismatch), response.error());
+        assert_eq!(
+            vec![
+                FieldViolation {
+                    field: "message.token".to_string(),
+                    description: "Invalid registration token".to_string(),
+                },
+                FieldViolation {
+                    field: "message.data".to_string(),
+                    description: "Invalid value".to_string(),
+                },
+            ],
+            response.field_violations()
+        );
+        assert_eq!(None, response.quota_kind());
+    }
+
+    #[test]
+    fn test_error_details_are_empty_without_details_array() {
+        let json = serde_json::json!({ "error": { "status": "QUOTA_EXCEEDED" } });
+        let response = FcmResponse::new(429, json.as_object().unwrap().clone(), None);
+
+        assert!(response.error_details().is_empty());
+        assert_eq!(None, response.fcm_error_code());
+        assert!(response.field_violations().is_empty());
+    }
+
+    #[test]
+    fn test_next_wait_grows_initial_wait_time_exponentially() {
+        let action = RecomendedAction::Retry(RecomendedWaitTime::InitialWaitTime(Duration::from_secs(10)));
+
+        assert_eq!(Some(Duration::from_secs(10)), action.next_wait(0));
+        assert_eq!(Some(Duration::from_secs(20)), action.next_wait(1));
+        assert_eq!(Some(Duration::from_secs(40)), action.next_wait(2));
+        assert_eq!(Some(MAX_BACKOFF_WAIT_TIME), action.next_wait(20));
+        assert_eq!(Some(MAX_BACKOFF_WAIT_TIME), action.next_wait(u32::MAX));
+    }
+
+    #[test]
+    fn test_next_wait_with_clock_uses_clock_for_retry_after_date() {
+        #[derive(Debug)]
+        struct FixedClock(DateTime<Utc>);
+
+        impl Clock for FixedClock {
+            fn now(&self) -> DateTime<Utc> {
+                self.0
+            }
+        }
+
+        let clock = FixedClock(DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap().to_utc());
+        let retry_after = RetryAfter::DateTime(DateTime::parse_from_rfc3339("2024-01-01T00:01:00Z").unwrap());
+        let action = RecomendedAction::Retry(RecomendedWaitTime::SpecificWaitTime(&retry_after));
+
+        assert_eq!(Some(Duration::from_secs(60)), action.next_wait_with_clock(3, &clock));
+        assert_eq!(
+            Duration::from_secs(30),
+            retry_after.wait_time_clamped_with_clock(Duration::ZERO, Duration::from_secs(30), &clock)
+        );
+    }
+
+    #[test]
+    fn test_next_wait_keeps_specific_wait_time() {
+        let retry_after = RetryAfter::Delay(Duration::from_secs(30));
+        let action = RecomendedAction::ReduceMessageRateAndRetry(RecomendedWaitTime::SpecificWaitTime(&retry_after));
+
+        assert_eq!(Some(Duration::from_secs(30)), action.next_wait(0));
+        assert_eq!(Some(Duration::from_secs(30)), action.next_wait(2));
+    }
+
+    #[test]
+    fn test_next_wait_is_none_for_non_retryable_action() {
+        assert_eq!(None, RecomendedAction::RemoveFcmAppToken.next_wait(0));
+        assert_eq!(None, RecomendedAction::FixMessageContent.next_wait(1));
+    }
+
+    #[test]
+    fn test_indicates_misconfiguration() {
+        for &(error, _, status_strings, _) in FCM_RESPONSE_ERROR_TABLE {
+            let json = serde_json::json!({ "error": { "status": status_strings[0] } });
+            let response = FcmResponse::new(400, json.as_object().unwrap().clone(), None);
+            let expected = matches!(
+                error,
+                FcmResponseError::SenderIdMismatch | FcmResponseError::ThirdPartyAuth
+            );
+            assert_eq!(expected, response.indicates_misconfiguration(), "{}", error);
+        }
+
+        let json = serde_json::json!({ "name": "projects/p/messages/1" });
+        let response = FcmResponse::new(200, json.as_object().unwrap().clone(), None);
+        assert!(!response.indicates_misconfiguration());
+    }
+
+    #[test]
+    fn test_details_are_parsed_by_type() {
+        let json = serde_json::json!({
+            "error": {
+                "status": "RESOURCE_EXHAUSTED",
+                "details": [
+                    {
+                        "@type": "type.googleapis.com/google.firebase.fcm.v1.FcmError",
+                        "errorCode": "QUOTA_EXCEEDED"
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.rpc.BadRequest",
+                        "fieldViolations": [{ "field": "message.data", "description": "Invalid value" }]
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.rpc.QuotaFailure",
+                        "violations": [{ "subject": "device", "description": "Device message rate exceeded" }]
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.rpc.Help",
+                        "links": [{ "description": "Quotas", "url": "https://example.com/quotas" }]
+                    },
+                    { "@type": "type.googleapis.com/google.rpc.ErrorInfo", "reason": "RATE_LIMIT" },
+                    { "@type": "type.googleapis.com/google.firebase.fcm.v1.FcmError", "errorCode": 1 }
+                ]
+            }
+        });
+        let response = FcmResponse::new(429, json.as_object().unwrap().clone(), None);
+
+        assert_eq!(
+            vec![
+                ErrorDetail::FcmError {
+                    error_code: "QUOTA_EXCEEDED".to_string(),
+                },
+                ErrorDetail::BadRequest {
+                    field_violations: vec![FieldViolation {
+                        field: "message.data".to_string(),
+                        description: "Invalid value".to_string(),
+                    }],
+                },
+                ErrorDetail::QuotaFailure {
+                    violations: vec![QuotaViolation {
+                        subject: "device".to_string(),
+                        description: "Device message rate exceeded".to_string(),
+                    }],
+                },
+                ErrorDetail::Help {
+                    links: vec![HelpLink {
+                        description: "Quotas".to_string(),
+                        url: "https://example.com/quotas".to_string(),
+                    }],
+                },
+                ErrorDetail::Unknown(json["error"]["details"][4].clone()),
+                ErrorDetail::Unknown(json["error"]["details"][5].clone()),
+            ],
+            response.details()
+        );
+        assert_eq!(Some("device".to_string()), response.quota_kind());
+    }
+
+    #[test]
+    fn test_next_backoff_grows_exponentially_up_to_max() {
+        let wait_time = RecomendedWaitTime::InitialWaitTime(Duration::from_secs(10));
+
+        assert_eq!(Duration::from_secs(10), wait_time.next_backoff(0, false));
+        assert_eq!(Duration::from_secs(20), wait_time.next_backoff(1, false));
+        assert_eq!(Duration::from_secs(40), wait_time.next_backoff(2, false));
+        assert_eq!(MAX_BACKOFF_WAIT_TIME, wait_time.next_backoff(20, false));
+        assert_eq!(MAX_BACKOFF_WAIT_TIME, wait_time.next_backoff(u32::MAX, false));
+    }
+
+    #[test]
+    fn test_next_backoff_with_jitter_is_between_half_and_full_wait_time() {
+        let wait_time = RecomendedWaitTime::InitialWaitTime(Duration::from_secs(10));
+
+        for _ in 0..100 {
+            let backoff = wait_time.next_backoff(2, true);
+            assert!(backoff >= Duration::from_secs(20) && backoff <= Duration::from_secs(40));
+        }
+    }
+
+    #[test]
+    fn test_next_backoff_uses_specific_wait_time() {
+        let retry_after = RetryAfter::Delay(Duration::from_secs(30));
+        let wait_time = RecomendedWaitTime::SpecificWaitTime(&retry_after);
+
+        assert_eq!(Duration::from_secs(30), wait_time.next_backoff(0, true));
+        assert_eq!(Duration::from_secs(30), wait_time.next_backoff(3, false));
+    }
 }
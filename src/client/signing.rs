@@ -0,0 +1,95 @@
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue};
+use sha2::Sha256;
+
+/// Configuration of [RequestSigner] which can be debug printed without
+/// revealing the key.
+#[derive(Clone)]
+pub(crate) struct RequestSigningConfig {
+    pub key: Vec<u8>,
+    pub header_name: String,
+}
+
+impl fmt::Debug for RequestSigningConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestSigningConfig")
+            .field("key", &"<redacted>")
+            .field("header_name", &self.header_name)
+            .finish()
+    }
+}
+
+/// Adds HMAC-SHA256 signature of the request body to a request header.
+pub(crate) struct RequestSigner {
+    key: Vec<u8>,
+    header_name: HeaderName,
+}
+
+impl RequestSigner {
+    /// Returns `None` if the header name is not valid.
+    pub fn new(config: RequestSigningConfig) -> Option<Self> {
+        let header_name = HeaderName::from_bytes(config.header_name.as_bytes()).ok()?;
+        Some(Self {
+            key: config.key,
+            header_name,
+        })
+    }
+
+    pub fn sign(&self, mut request: reqwest::Request) -> reqwest::Request {
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap_or_default();
+        let signature = hmac_sha256_hex(&self.key, body);
+        let value = HeaderValue::from_str(&signature).expect("Hex is valid header value");
+        request.headers_mut().insert(self.header_name.clone(), value);
+        request
+    }
+}
+
+/// Lowercase hexadecimal HMAC-SHA256 of `data`.
+pub(crate) fn hmac_sha256_hex(key: &[u8], data: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test case 1 of RFC 4231.
+    #[test]
+    fn should_compute_hmac_sha256_test_vector() {
+        assert_eq!(
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            hmac_sha256_hex(&[0x0b; 20], b"Hi There")
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_header_name() {
+        let config = |header_name: &str| RequestSigningConfig {
+            key: b"key".to_vec(),
+            header_name: header_name.to_string(),
+        };
+
+        assert!(RequestSigner::new(config("x signature")).is_none());
+        assert!(RequestSigner::new(config("x-signature")).is_some());
+    }
+
+    #[test]
+    fn should_not_debug_print_key() {
+        let config = RequestSigningConfig {
+            key: b"secret".to_vec(),
+            header_name: "x-signature".to_string(),
+        };
+
+        let debug = format!("{:?}", config);
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("115, 101"));
+    }
+}
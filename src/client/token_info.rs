@@ -0,0 +1,48 @@
+use serde::Deserialize;
+
+/// Information about a registration token from the Instance ID API.
+/// <https://developers.google.com/instance-id/reference/server#get_information_about_app_instances>
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    /// Package name or bundle ID of the app.
+    pub application: Option<String>,
+    /// `ANDROID`, `IOS` or `CHROME`.
+    pub platform: Option<String>,
+    /// Topics which the token is subscribed to.
+    #[serde(rename = "rel", default, deserialize_with = "deserialize_topics")]
+    pub topics: Vec<TopicSubscription>,
+}
+
+/// Topic subscription of [TokenInfo].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicSubscription {
+    pub name: String,
+    /// Date when the token was subscribed, e.g. `2015-07-30`.
+    pub add_date: Option<String>,
+}
+
+/// Deserialize `{"topics": {"<name>": {"addDate": "<date>"}}}`.
+fn deserialize_topics<'de, D>(deserializer: D) -> Result<Vec<TopicSubscription>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Relations {
+        #[serde(default)]
+        topics: serde_json::Map<String, serde_json::Value>,
+    }
+
+    let relations = Relations::deserialize(deserializer)?;
+    Ok(relations
+        .topics
+        .into_iter()
+        .map(|(name, details)| TopicSubscription {
+            name,
+            add_date: details
+                .get("addDate")
+                .and_then(|date| date.as_str())
+                .map(str::to_string),
+        })
+        .collect())
+}
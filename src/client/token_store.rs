@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use yup_oauth2::storage::{TokenInfo, TokenStorage};
+
+/// Custom storage for OAuth tokens, for example Redis or a database, so
+/// that tokens can be shared between processes and survive restarts.
+///
+/// The stored data is opaque bytes which contain every cached token.
+/// Set with [crate::FcmClientBuilder::token_store]. Implement the trait
+/// with the [`async_trait`](https://docs.rs/async-trait) attribute.
+#[async_trait::async_trait]
+pub trait TokenStore: fmt::Debug + Send + Sync {
+    /// Load data which was previously given to [TokenStore::store].
+    /// Returns `None` if there is no stored data.
+    async fn load(&self) -> Option<Vec<u8>>;
+
+    /// Store `data`, replacing previously stored data.
+    async fn store(&self, data: &[u8]);
+}
+
+/// Tokens keyed by their sorted and space separated scopes.
+type StoredTokens = HashMap<String, TokenInfo>;
+
+/// Adapts [TokenStore] to the token storage of the authenticator.
+pub(crate) struct TokenStoreAdapter(pub(crate) Arc<dyn TokenStore>);
+
+impl TokenStoreAdapter {
+    async fn load_tokens(&self) -> StoredTokens {
+        match self.0.load().await {
+            Some(data) => serde_json::from_slice(&data).unwrap_or_default(),
+            None => StoredTokens::new(),
+        }
+    }
+}
+
+fn scopes_key(scopes: &[&str]) -> String {
+    let mut scopes = scopes.to_vec();
+    scopes.sort_unstable();
+    scopes.join(" ")
+}
+
+#[async_trait::async_trait]
+impl TokenStorage for TokenStoreAdapter {
+    async fn set(&self, scopes: &[&str], token: TokenInfo) -> anyhow::Result<()> {
+        let mut tokens = self.load_tokens().await;
+        tokens.insert(scopes_key(scopes), token);
+        self.0.store(&serde_json::to_vec(&tokens)?).await;
+        Ok(())
+    }
+
+    async fn get(&self, scopes: &[&str]) -> Option<TokenInfo> {
+        self.load_tokens().await.remove(&scopes_key(scopes))
+    }
+}
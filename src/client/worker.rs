@@ -0,0 +1,38 @@
+use futures::stream::{self, StreamExt};
+use tokio::sync::mpsc;
+
+use super::{response::FcmResponse, FcmClient, FcmClientError};
+use crate::message::Message;
+
+impl FcmClient {
+    /// Send every message received from `messages` until the channel is
+    /// closed and all sends have completed. At most `concurrency`
+    /// messages are sent at the same time.
+    ///
+    /// Each message is sent to `outcomes` with its result in the order
+    /// the sends complete. Results are dropped if `outcomes` is closed,
+    /// but the remaining messages are still sent.
+    pub async fn run_worker(
+        &self,
+        messages: mpsc::Receiver<Message>,
+        outcomes: mpsc::Sender<(Message, Result<FcmResponse, FcmClientError>)>,
+        concurrency: usize,
+    ) {
+        stream::unfold(messages, |mut messages| async move {
+            let message = messages.recv().await?;
+            Some((message, messages))
+        })
+        .map(|message| async move {
+            let result = self.send(&message).await;
+            (message, result)
+        })
+        .buffer_unordered(concurrency.max(1))
+        .for_each(|outcome| {
+            let outcomes = &outcomes;
+            async move {
+                let _ = outcomes.send(outcome).await;
+            }
+        })
+        .await
+    }
+}
@@ -0,0 +1,79 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Maximum length of an analytics label.
+pub const MAX_ANALYTICS_LABEL_LENGTH: usize = 50;
+
+/// Label associated with the message's analytics data. The label must
+/// match the FCM pattern `^[a-zA-Z0-9-_.~%]{1,50}$`.
+///
+/// ```rust
+/// use std::convert::TryFrom;
+/// use fcm::message::AnalyticsLabel;
+///
+/// assert!(AnalyticsLabel::try_from("campaign-1").is_ok());
+/// assert!(AnalyticsLabel::try_from("campaign 1").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(transparent)]
+pub struct AnalyticsLabel(String);
+
+/// Error of [AnalyticsLabel] construction.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("Analytics label does not match pattern ^[a-zA-Z0-9-_.~%]{{1,50}}$: {0:?}")]
+pub struct AnalyticsLabelError(pub String);
+
+impl AnalyticsLabel {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for AnalyticsLabel {
+    type Error = AnalyticsLabelError;
+
+    fn try_from(label: String) -> Result<Self, Self::Error> {
+        let is_valid = !label.is_empty()
+            && label.len() <= MAX_ANALYTICS_LABEL_LENGTH
+            && label
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~' | '%'));
+        if is_valid {
+            Ok(AnalyticsLabel(label))
+        } else {
+            Err(AnalyticsLabelError(label))
+        }
+    }
+}
+
+impl TryFrom<&str> for AnalyticsLabel {
+    type Error = AnalyticsLabelError;
+
+    fn try_from(label: &str) -> Result<Self, Self::Error> {
+        AnalyticsLabel::try_from(label.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for AnalyticsLabel {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let label = String::deserialize(deserializer)?;
+        AnalyticsLabel::try_from(label).map_err(serde::de::Error::custom)
+    }
+}
+
+impl AsRef<str> for AnalyticsLabel {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for AnalyticsLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,50 @@
+use serde_json::{Map, Value};
+
+use super::Message;
+
+/// Sort keys of all JSON objects in `value` recursively, so that equal
+/// values have equal serialized form.
+pub(crate) fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<(String, Value)> = object.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect::<Map<String, Value>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(canonicalize).collect()),
+        value => value,
+    }
+}
+
+impl Message {
+    /// Stable hash of the message without its target, for example to
+    /// deduplicate a message sent to many targets or to correlate log
+    /// lines. Key order of JSON objects does not affect the hash.
+    ///
+    /// The hash is the 64-bit FNV-1a hash of the canonical JSON form, so
+    /// it stays the same between program runs and crate versions as
+    /// long as the JSON form of the message does not change.
+    pub fn fingerprint(&self) -> u64 {
+        let mut json = serde_json::to_value(self).expect("Message serialization failed");
+        if let Value::Object(object) = &mut json {
+            for key in ["token", "topic", "condition"] {
+                object.remove(key);
+            }
+        }
+        let bytes = serde_json::to_vec(&canonicalize(json)).expect("Message serialization failed");
+        fnv1a(&bytes)
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes
+        .iter()
+        .fold(OFFSET_BASIS, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+}
@@ -0,0 +1,44 @@
+use std::io::Write;
+
+use base64::Engine;
+use flate2::{write::GzEncoder, Compression};
+use serde_json::Value;
+
+use super::Message;
+
+/// Value of the `<key>_encoding` data field which
+/// [Message::compress_data_field] adds.
+pub const DATA_ENCODING_GZIP_BASE64: &str = "gzip+base64";
+
+impl Message {
+    /// Replace string value of data field `key` with the value gzip
+    /// compressed and base64 (standard alphabet, padded) encoded, and add
+    /// field `<key>_encoding` with value `"gzip+base64"` so that the app
+    /// knows to decode it. Useful for large values, as FCM limits the
+    /// payload to 4KB.
+    ///
+    /// Returns `false` and leaves the message unchanged if `data` does not
+    /// have string value for `key`.
+    pub fn compress_data_field(&mut self, key: &str) -> bool {
+        let data = match &mut self.data {
+            Some(Value::Object(data)) => data,
+            _ => return false,
+        };
+        let value = match data.get(key) {
+            Some(Value::String(value)) => value,
+            _ => return false,
+        };
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(value.as_bytes()).expect("Writing to Vec failed");
+        let compressed = encoder.finish().expect("Writing to Vec failed");
+        let encoded = base64::engine::general_purpose::STANDARD.encode(compressed);
+
+        data.insert(key.to_string(), Value::String(encoded));
+        data.insert(
+            format!("{}_encoding", key),
+            Value::String(DATA_ENCODING_GZIP_BASE64.to_string()),
+        );
+        true
+    }
+}
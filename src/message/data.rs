@@ -0,0 +1,40 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::Message;
+
+/// Error cases of [Message::set_data_from].
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum DataError {
+    #[error("Data serialization failed: {0}")]
+    Serialization(String),
+    #[error("Data is not serialized to a JSON object")]
+    NotObject,
+    #[error("Data value of key {0:?} is an array or an object")]
+    NestedValue(String),
+}
+
+impl Message {
+    /// Set `data` from a typed payload, for example a struct. `value` must
+    /// serialize to a flat JSON object. Numbers and booleans are converted
+    /// to strings, as FCM requires, and `null` values are left out.
+    pub fn set_data_from<T: Serialize>(&mut self, value: &T) -> Result<(), DataError> {
+        let value = serde_json::to_value(value).map_err(|error| DataError::Serialization(error.to_string()))?;
+        let object = match value {
+            Value::Object(object) => object,
+            _ => return Err(DataError::NotObject),
+        };
+        let mut data = Map::new();
+        for (key, value) in object {
+            let value = match value {
+                Value::Null => continue,
+                Value::String(value) => value,
+                Value::Bool(_) | Value::Number(_) => value.to_string(),
+                Value::Array(_) | Value::Object(_) => return Err(DataError::NestedValue(key)),
+            };
+            data.insert(key, Value::String(value));
+        }
+        self.data = Some(Value::Object(data));
+        Ok(())
+    }
+}
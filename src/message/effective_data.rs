@@ -0,0 +1,36 @@
+use serde_json::{Map, Value};
+
+use super::Message;
+
+impl Message {
+    /// Data which an Android device receives: top-level `data` merged
+    /// with `android.data`. Keys of `android.data` override keys of
+    /// top-level `data`.
+    pub fn effective_android_data(&self) -> Map<String, Value> {
+        merge_data(
+            &self.data,
+            self.android.as_ref().and_then(|android| android.data.as_ref()),
+        )
+    }
+
+    /// Data which a web push receiver receives: top-level `data` merged
+    /// with `webpush.data`. Keys of `webpush.data` override keys of
+    /// top-level `data`.
+    pub fn effective_webpush_data(&self) -> Map<String, Value> {
+        merge_data(
+            &self.data,
+            self.webpush.as_ref().and_then(|webpush| webpush.data.as_ref()),
+        )
+    }
+}
+
+/// Values which are not JSON objects are ignored.
+fn merge_data(data: &Option<Value>, platform_data: Option<&Value>) -> Map<String, Value> {
+    let mut merged = Map::new();
+    for data in data.iter().chain(platform_data) {
+        if let Value::Object(data) = data {
+            merged.extend(data.iter().map(|(key, value)| (key.clone(), value.clone())));
+        }
+    }
+    merged
+}
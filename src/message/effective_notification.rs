@@ -0,0 +1,134 @@
+use serde_json::{Map, Value};
+
+use super::{AndroidConfig, AndroidNotification, ApnsConfig, Message};
+
+/// Platform which receives a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Android,
+    Apns,
+    Webpush,
+}
+
+/// Notification texts and image which a platform displays, see
+/// [Message::resolve_notification].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ResolvedNotification {
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub image: Option<String>,
+}
+
+impl Message {
+    /// Notification which `platform` displays. Every field set in the
+    /// platform specific notification overrides the same field of
+    /// top-level `notification`:
+    ///
+    /// | Platform | Platform specific fields                                     |
+    /// |----------|--------------------------------------------------------------|
+    /// | Android  | `android.notification` `title`, `body` and `image`           |
+    /// | APNs     | `aps.alert` `title` and `body` (string alert is the body), `apns.fcm_options.image` |
+    /// | Web push | `webpush.notification` `title`, `body` and `image`           |
+    pub fn resolve_notification(&self, platform: Platform) -> ResolvedNotification {
+        let common = self.notification.as_ref();
+        let mut resolved = ResolvedNotification {
+            title: common.and_then(|notification| notification.title.clone()),
+            body: common.and_then(|notification| notification.body.clone()),
+            image: common.and_then(|notification| notification.image.clone()),
+        };
+
+        let overrides = match platform {
+            Platform::Android => self
+                .android
+                .as_ref()
+                .and_then(|android| android.notification.as_ref())
+                .map(|notification| ResolvedNotification {
+                    title: notification.title.clone(),
+                    body: notification.body.clone(),
+                    image: notification.image.clone(),
+                }),
+            Platform::Apns => self.apns.as_ref().map(|apns| {
+                let alert = apns.payload.as_ref().and_then(|payload| payload.pointer("/aps/alert"));
+                let (title, body) = match alert {
+                    Some(Value::String(body)) => (None, Some(body.clone())),
+                    Some(alert) => (string_field(alert, "title"), string_field(alert, "body")),
+                    None => (None, None),
+                };
+                ResolvedNotification {
+                    title,
+                    body,
+                    image: apns.fcm_options.as_ref().and_then(|options| options.image.clone()),
+                }
+            }),
+            Platform::Webpush => self
+                .webpush
+                .as_ref()
+                .and_then(|webpush| webpush.notification.as_ref())
+                .map(|notification| ResolvedNotification {
+                    title: string_field(notification, "title"),
+                    body: string_field(notification, "body"),
+                    image: string_field(notification, "image"),
+                }),
+        };
+
+        if let Some(overrides) = overrides {
+            resolved.title = overrides.title.or(resolved.title);
+            resolved.body = overrides.body.or(resolved.body);
+            resolved.image = overrides.image.or(resolved.image);
+        }
+        resolved
+    }
+
+    /// Copy top-level `notification` to the platforms which do not have
+    /// their own notification, so that they do not depend on how FCM
+    /// fills in the platform notification:
+    ///
+    /// - `android.notification` is set to the `title`, `body` and `image`
+    /// - `aps.alert` of the APNs payload is set to the `title` and `body`,
+    ///   unless the payload is a background notification
+    ///   (`content-available: 1`)
+    ///
+    /// Missing platform configs are created. Web push is not changed.
+    pub fn merge_common_notification_into_platforms(mut self) -> Message {
+        let common = match &self.notification {
+            Some(notification) => notification.clone(),
+            None => return self,
+        };
+
+        let android = self.android.get_or_insert_with(AndroidConfig::default);
+        if android.notification.is_none() {
+            android.notification = Some(AndroidNotification {
+                title: common.title.clone(),
+                body: common.body.clone(),
+                image: common.image.clone(),
+                ..Default::default()
+            });
+        }
+
+        let apns = self.apns.get_or_insert_with(ApnsConfig::default);
+        let payload = apns.payload.get_or_insert_with(|| Value::Object(Map::new()));
+        if let Some(payload) = payload.as_object_mut() {
+            let aps = payload.entry("aps").or_insert_with(|| Value::Object(Map::new()));
+            if let Some(aps) = aps.as_object_mut() {
+                let is_silent = aps.get("content-available").and_then(Value::as_i64) == Some(1);
+                if !is_silent && !aps.contains_key("alert") {
+                    let mut alert = Map::new();
+                    if let Some(title) = common.title {
+                        alert.insert("title".to_string(), Value::String(title));
+                    }
+                    if let Some(body) = common.body {
+                        alert.insert("body".to_string(), Value::String(body));
+                    }
+                    if !alert.is_empty() {
+                        aps.insert("alert".to_string(), Value::Object(alert));
+                    }
+                }
+            }
+        }
+        self
+    }
+}
+
+fn string_field(object: &Value, key: &str) -> Option<String> {
+    object.get(key).and_then(Value::as_str).map(str::to_string)
+}
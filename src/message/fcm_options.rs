@@ -1,8 +1,16 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Serialize)]
+use crate::message::AnalyticsLabel;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#fcmoptions>
+///
+/// FCM API does not have an image option for all platforms. Use
+/// [crate::message::Notification::image], or
+/// [crate::message::AndroidNotification::image] and
+/// [crate::message::ApnsFcmOptions::image] for platform specific images.
 pub struct FcmOptions {
     /// Label associated with the message's analytics data.
-    pub analytics_label: String,
+    #[serde(alias = "analyticsLabel")]
+    pub analytics_label: AnalyticsLabel,
 }
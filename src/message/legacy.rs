@@ -0,0 +1,55 @@
+use serde_json::{Map, Value};
+
+use super::{Message, Target};
+
+/// Error cases of [Message::to_legacy_json].
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum LegacyConversionError {
+    #[error("Condition targets are not supported in legacy JSON")]
+    ConditionTarget,
+    #[error("Field is not supported in legacy JSON: {0}")]
+    UnsupportedField(&'static str),
+}
+
+impl Message {
+    /// Convert the message to JSON of the legacy FCM HTTP API
+    /// (`to`, `notification` and `data` fields) for systems which still
+    /// consume it. Token target is set to `to` as is and topic target as
+    /// `/topics/<name>`.
+    ///
+    /// Condition targets and fields which the legacy format does not
+    /// have (`android`, `webpush`, `apns` and `fcm_options`) are errors.
+    pub fn to_legacy_json(&self) -> Result<Value, LegacyConversionError> {
+        let unsupported_field = if self.android.is_some() {
+            Some("android")
+        } else if self.webpush.is_some() {
+            Some("webpush")
+        } else if self.apns.is_some() {
+            Some("apns")
+        } else if self.fcm_options.is_some() {
+            Some("fcm_options")
+        } else {
+            None
+        };
+        if let Some(field) = unsupported_field {
+            return Err(LegacyConversionError::UnsupportedField(field));
+        }
+
+        let to = match &self.target {
+            Target::Token(token) => token.clone(),
+            Target::Topic(topic) => format!("/topics/{}", topic),
+            Target::Condition(_) => return Err(LegacyConversionError::ConditionTarget),
+        };
+
+        let mut legacy = Map::new();
+        legacy.insert("to".to_string(), Value::String(to));
+        if let Some(notification) = &self.notification {
+            let notification = serde_json::to_value(notification).expect("Notification serialization failed");
+            legacy.insert("notification".to_string(), notification);
+        }
+        if let Some(data) = &self.data {
+            legacy.insert("data".to_string(), data.clone());
+        }
+        Ok(Value::Object(legacy))
+    }
+}
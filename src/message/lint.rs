@@ -0,0 +1,50 @@
+use super::Message;
+
+/// Non-fatal warning about message content. FCM accepts the message but
+/// it most likely does not work as intended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    /// `android.direct_boot_ok` is `true` but the message has a
+    /// notification. Notifications are not displayed while the device
+    /// is in direct boot mode, so only data is delivered.
+    DirectBootOkWithNotification,
+
+    /// `android.notification.click_action` is set. It is the intent
+    /// filter based click handling of the legacy FCM API; prefer opening
+    /// the launcher activity and routing with `data`.
+    AndroidClickAction,
+
+    /// `webpush.notification.click_action` is set. It is from the legacy
+    /// FCM API and is superseded by `webpush.fcm_options.link`.
+    WebpushClickAction,
+}
+
+impl Message {
+    /// Check message for likely misconfigurations and deprecated or
+    /// discouraged fields. The lints are advisory; FCM accepts the
+    /// message.
+    pub fn lint(&self) -> Vec<Lint> {
+        let mut lints = Vec::new();
+
+        if let Some(android) = &self.android {
+            let has_notification = self.notification.is_some() || android.notification.is_some();
+            if android.direct_boot_ok == Some(true) && has_notification {
+                lints.push(Lint::DirectBootOkWithNotification);
+            }
+            let has_click_action = android
+                .notification
+                .as_ref()
+                .is_some_and(|notification| notification.click_action.is_some());
+            if has_click_action {
+                lints.push(Lint::AndroidClickAction);
+            }
+        }
+
+        let webpush_notification = self.webpush.as_ref().and_then(|webpush| webpush.notification.as_ref());
+        if webpush_notification.is_some_and(|notification| notification.get("click_action").is_some()) {
+            lints.push(Lint::WebpushClickAction);
+        }
+
+        lints
+    }
+}
@@ -1,5 +1,25 @@
+//! Message configs serialize with the snake_case field names, which FCM
+//! accepts through its proto JSON mapping. The camelCase names of the REST
+//! API reference are only accepted as aliases when deserializing.
+
+pub(crate) mod analytics_label;
+pub(crate) mod canonical;
+#[cfg(feature = "compression")]
+pub(crate) mod compress;
+pub(crate) mod data;
+pub(crate) mod effective_data;
+pub(crate) mod effective_notification;
 pub(crate) mod fcm_options;
+#[cfg(feature = "legacy-json")]
+pub(crate) mod legacy;
+pub(crate) mod lint;
+pub(crate) mod normalize;
+pub(crate) mod size;
 pub(crate) mod target;
+pub(crate) mod template;
+pub(crate) mod topic_condition;
+pub(crate) mod ttl;
+pub(crate) mod validate;
 
 #[cfg(test)]
 mod tests;
@@ -9,8 +29,22 @@ use serde::Serialize;
 use serde::Serializer;
 use serde_json::Value;
 
+pub use crate::message::analytics_label::*;
+#[cfg(feature = "compression")]
+pub use crate::message::compress::*;
+pub use crate::message::data::*;
+pub use crate::message::effective_notification::*;
 pub use crate::message::fcm_options::*;
+#[cfg(feature = "legacy-json")]
+pub use crate::message::legacy::*;
+pub use crate::message::lint::*;
+pub use crate::message::normalize::*;
+pub use crate::message::size::*;
 pub use crate::message::target::*;
+pub use crate::message::template::*;
+pub use crate::message::topic_condition::*;
+pub use crate::message::ttl::*;
+pub use crate::message::validate::*;
 
 pub use crate::notification::*;
 
@@ -42,7 +76,7 @@ where
     map.end()
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 /// A `Message` instance is the main object to send to the FCM API.
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#resource:-message>
 pub struct Message {
@@ -75,6 +109,95 @@ pub struct Message {
     pub target: Target,
 }
 
+impl Message {
+    /// Message which contains only `data`, for example for silent
+    /// background sync. See also [Message::is_data_only].
+    pub fn data_only(target: Target, data: Value) -> Message {
+        Message {
+            data: Some(data),
+            notification: None,
+            android: None,
+            webpush: None,
+            apns: None,
+            fcm_options: None,
+            target,
+        }
+    }
+
+    /// Returns `true` if the message does not contain anything which
+    /// displays a notification: top-level, Android or web push
+    /// notification or APNs alert.
+    pub fn is_data_only(&self) -> bool {
+        !self.has_notification()
+    }
+
+    /// Returns `true` if the message displays a notification (see
+    /// [Message::is_data_only]) and has no data. When the app is in the
+    /// background the system displays the notification and the app is
+    /// not woken up.
+    pub fn is_notification_message(&self) -> bool {
+        self.has_notification() && !self.has_data()
+    }
+
+    /// Returns `true` if the message has data (top-level, Android or web
+    /// push) and does not display a notification. The app handles the
+    /// message also when it is in the background.
+    pub fn is_data_message(&self) -> bool {
+        self.has_data() && !self.has_notification()
+    }
+
+    /// Returns `true` if the message both displays a notification and
+    /// has data. When the app is in the background the system displays
+    /// the notification and the data is given to the app only when the
+    /// user opens the notification.
+    pub fn is_mixed(&self) -> bool {
+        self.has_notification() && self.has_data()
+    }
+
+    fn has_data(&self) -> bool {
+        self.data.is_some()
+            || self.android.as_ref().is_some_and(|android| android.data.is_some())
+            || self.webpush.as_ref().is_some_and(|webpush| webpush.data.is_some())
+    }
+
+    fn has_notification(&self) -> bool {
+        let has_android_notification = self
+            .android
+            .as_ref()
+            .is_some_and(|android| android.notification.is_some());
+        let has_webpush_notification = self
+            .webpush
+            .as_ref()
+            .is_some_and(|webpush| webpush.notification.is_some());
+        let has_apns_alert = self
+            .apns
+            .as_ref()
+            .and_then(|apns| apns.payload.as_ref())
+            .is_some_and(|payload| payload.pointer("/aps/alert").is_some());
+        self.notification.is_some() || has_android_notification || has_webpush_notification || has_apns_alert
+    }
+
+    /// Replace non-string values in `data` object with their JSON text,
+    /// so that for example `{"count": 5, "flag": true}` becomes
+    /// `{"count": "5", "flag": "true"}`. FCM requires that data values
+    /// are strings.
+    pub fn stringify_data(mut self) -> Message {
+        if let Some(Value::Object(data)) = &mut self.data {
+            for value in data.values_mut() {
+                if !value.is_string() {
+                    *value = Value::String(value.to_string());
+                }
+            }
+        }
+        self
+    }
+
+    /// Request body which is sent to FCM as indented JSON.
+    pub fn to_pretty_json(&self) -> String {
+        serde_json::to_string_pretty(&MessageWrapper::new(self, false)).expect("Message serialization failed")
+    }
+}
+
 impl AsRef<Message> for Message {
     fn as_ref(&self) -> &Message {
         self
@@ -87,17 +210,25 @@ fn is_validate_only_default(b: &bool) -> bool {
 }
 
 #[derive(Serialize)]
-pub(crate) struct MessageWrapper<'a> {
+pub(crate) struct MessageWrapper<'a, T: ?Sized = Message> {
     #[serde(skip_serializing_if = "is_validate_only_default")]
     validate_only: bool,
-    message: &'a Message,
+    message: &'a T,
 }
 
-impl MessageWrapper<'_> {
-    pub fn new(message: &Message, dry_run: bool) -> MessageWrapper {
+impl<T: ?Sized> MessageWrapper<'_, T> {
+    pub fn new(message: &T, dry_run: bool) -> MessageWrapper<'_, T> {
         MessageWrapper {
             validate_only: dry_run,
             message,
         }
     }
 }
+
+/// Request body of the FCM `messages:send` API
+/// (`{"validate_only": true, "message": ...}`), which is what
+/// [crate::FcmClient] sends. Useful for custom transports, for example
+/// a queue which stores the request bodies for sending later.
+pub fn wrap_message(message: &Message, dry_run: bool) -> impl Serialize + '_ {
+    MessageWrapper::new(message, dry_run)
+}
@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+use super::ttl::apns_expiration;
+use super::{AndroidMessagePriority, ApnsConfig, Message, APNS_EXPIRATION_HEADER};
+use crate::{Clock, SystemClock};
+
+/// APNs request header for message priority.
+pub const APNS_PRIORITY_HEADER: &str = "apns-priority";
+
+/// Optional passes of [Message::normalize_with_options].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Set `aps.sound` of the APNs payload from the Android notification
+    /// `sound`, or to `"default"` if the Android notification uses the
+    /// default sound. Silent APNs payloads (`content-available: 1`
+    /// without alert) and payloads which have `aps.sound` are kept.
+    pub mirror_android_sound: bool,
+}
+
+impl Message {
+    /// Run all normalization passes:
+    ///
+    /// - [Message::normalize_priority]
+    /// - [ApnsConfig::auto_push_type]
+    /// - APNs expiration from Android TTL, see [Message::normalize_with_clock]
+    pub fn normalize(self) -> Message {
+        self.normalize_with_clock(&SystemClock)
+    }
+
+    /// Same as [Message::normalize] but the current time for the APNs
+    /// expiration is from `clock`.
+    ///
+    /// If the message has Android `ttl` but no `apns-expiration` header,
+    /// the header is set to the current UNIX time plus `ttl` seconds, so
+    /// that the message expires at the same time on both platforms.
+    /// `apns-expiration` is an absolute time, so normalize the message
+    /// just before sending it.
+    pub fn normalize_with_clock(mut self, clock: &dyn Clock) -> Message {
+        self.apply_normalization(clock.now(), NormalizeOptions::default());
+        self
+    }
+
+    /// Same as [Message::normalize] but also runs the optional passes
+    /// enabled in `options`.
+    pub fn normalize_with_options(mut self, options: NormalizeOptions) -> Message {
+        self.apply_normalization(SystemClock.now(), options);
+        self
+    }
+
+    pub(crate) fn apply_normalization(&mut self, now: DateTime<Utc>, options: NormalizeOptions) {
+        self.apply_priority_mapping();
+        self.apply_expiration_mapping(now);
+        if options.mirror_android_sound {
+            self.apply_sound_mapping();
+        }
+        if let Some(apns) = &mut self.apns {
+            apns.auto_push_type();
+        }
+    }
+
+    fn apply_sound_mapping(&mut self) {
+        let notification = match self.android.as_ref().and_then(|android| android.notification.as_ref()) {
+            Some(notification) => notification,
+            None => return,
+        };
+        let sound = match (&notification.sound, notification.default_sound) {
+            (Some(sound), _) => sound.clone(),
+            (None, Some(true)) => "default".to_string(),
+            (None, _) => return,
+        };
+
+        let apns = self.apns.get_or_insert_with(ApnsConfig::default);
+        let payload = apns.payload.get_or_insert_with(|| Value::Object(Map::new()));
+        if let Some(payload) = payload.as_object_mut() {
+            let aps = payload.entry("aps").or_insert_with(|| Value::Object(Map::new()));
+            if let Some(aps) = aps.as_object_mut() {
+                let is_silent =
+                    aps.get("content-available").and_then(Value::as_i64) == Some(1) && !aps.contains_key("alert");
+                if !is_silent && !aps.contains_key("sound") {
+                    aps.insert("sound".to_string(), Value::String(sound));
+                }
+            }
+        }
+    }
+
+    fn apply_expiration_mapping(&mut self, now: DateTime<Utc>) {
+        let ttl = match self.android.as_ref().and_then(|android| android.ttl_duration()) {
+            Some(Ok(ttl)) => ttl,
+            _ => return,
+        };
+
+        let apns = self.apns.get_or_insert_with(ApnsConfig::default);
+        if apns.header(APNS_EXPIRATION_HEADER).is_none() {
+            apns.set_header(APNS_EXPIRATION_HEADER, apns_expiration(now, ttl));
+        }
+    }
+
+    /// Set APNs priority from Android priority if the message does not
+    /// have APNs priority.
+    ///
+    /// | Android priority                   | `apns-priority` |
+    /// |------------------------------------|-----------------|
+    /// | [AndroidMessagePriority::High]     | `10` (immediate) |
+    /// | [AndroidMessagePriority::Normal]   | `5` (power considerations) |
+    pub fn normalize_priority(mut self) -> Message {
+        self.apply_priority_mapping();
+        self
+    }
+
+    fn apply_priority_mapping(&mut self) {
+        let android_priority = match self.android.as_ref().and_then(|android| android.priority.as_ref()) {
+            Some(priority) => priority,
+            None => return,
+        };
+        let apns_priority = match android_priority {
+            AndroidMessagePriority::High => "10",
+            AndroidMessagePriority::Normal => "5",
+        };
+
+        let apns = self.apns.get_or_insert_with(ApnsConfig::default);
+        if apns.header(APNS_PRIORITY_HEADER).is_none() {
+            apns.set_header(APNS_PRIORITY_HEADER, apns_priority);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use serde::Serialize;
+
+use super::Message;
+
+/// Maximum size in bytes of the `data` and `notification` payload of a
+/// message.
+/// <https://firebase.google.com/docs/cloud-messaging/concept-options#notifications_and_data_messages>
+pub const MAX_PAYLOAD_BYTES: usize = 4096;
+
+/// Sizes in bytes of the sections of a [Message] in its compact JSON
+/// form, see [Message::size_report]. Missing sections have size `0`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SizeReport {
+    /// `data` object.
+    pub data: usize,
+    /// `notification` object.
+    pub notification: usize,
+    /// `android` object.
+    pub android: usize,
+    /// `webpush` object.
+    pub webpush: usize,
+    /// `apns` object.
+    pub apns: usize,
+    /// Whole message, including the target and `fcm_options`.
+    pub total: usize,
+}
+
+impl SizeReport {
+    /// Returns `true` if `data` and `notification` together are at most
+    /// [MAX_PAYLOAD_BYTES]. Platform blocks are not checked, because FCM
+    /// does not document limits for them.
+    ///
+    /// FCM counts sizes slightly differently, so a message near the
+    /// limit might still be rejected.
+    pub fn within_limits(&self) -> bool {
+        self.data + self.notification <= MAX_PAYLOAD_BYTES
+    }
+}
+
+fn json_size<T: Serialize>(value: Option<&T>) -> usize {
+    value.map_or(0, |value| {
+        serde_json::to_vec(value).expect("Message serialization failed").len()
+    })
+}
+
+impl Message {
+    /// Sizes of the message sections, for example to find out which
+    /// section makes a message too big.
+    pub fn size_report(&self) -> SizeReport {
+        SizeReport {
+            data: json_size(self.data.as_ref()),
+            notification: json_size(self.notification.as_ref()),
+            android: json_size(self.android.as_ref()),
+            webpush: json_size(self.webpush.as_ref()),
+            apns: json_size(self.apns.as_ref()),
+            total: json_size(Some(self)),
+        }
+    }
+}
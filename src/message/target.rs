@@ -1,3 +1,6 @@
+use std::fmt;
+use std::str::FromStr;
+
 use serde::Serialize;
 
 /// Target to send a message to.
@@ -9,6 +12,19 @@ use serde::Serialize;
 /// Target::Topic("my-topic-name".to_string());
 /// Target::Condition("my-condition".to_string());
 /// ```
+///
+/// Targets can also be parsed from strings prefixed with `token:`,
+/// `topic:` or `condition:`. A string without one of these prefixes is
+/// a token, but a prefix in a different case (for example `Topic:`) is
+/// an error.
+///
+/// ```rust
+/// use fcm::message::{Target};
+///
+/// assert_eq!(Ok(Target::Topic("news".to_string())), "topic:news".parse());
+/// assert_eq!(Ok(Target::Token("abc123".to_string())), "abc123".parse());
+/// assert_eq!("topic:news", Target::Topic("news".to_string()).to_string());
+/// ```
 #[derive(Debug, Clone, Serialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum Target {
@@ -16,3 +32,72 @@ pub enum Target {
     Topic(String),
     Condition(String),
 }
+
+/// Error cases of [Target] construction.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum TargetError {
+    #[error("Topic name is not valid: {0:?}")]
+    InvalidTopicName(String),
+    #[error("Condition has {0} topics but maximum is {max}", max = MAX_CONDITION_TOPICS)]
+    TooManyTopics(usize),
+    #[error("Unknown target prefix: {0:?}")]
+    UnknownPrefix(String),
+    #[error("Condition placeholder has no substitution: {0:?}")]
+    UnknownPlaceholder(String),
+    #[error("Condition has an `&&` or `||` without operands")]
+    EmptyCondition,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Token(token) => write!(f, "token:{}", token),
+            Target::Topic(topic) => write!(f, "topic:{}", topic),
+            Target::Condition(condition) => write!(f, "condition:{}", condition),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = TargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, value) = match s.split_once(':') {
+            Some(prefixed) => prefixed,
+            None => return Ok(Target::Token(s.to_string())),
+        };
+        match prefix {
+            "token" => Ok(Target::Token(value.to_string())),
+            "topic" if is_valid_topic_name(value) => Ok(Target::Topic(value.to_string())),
+            "topic" => Err(TargetError::InvalidTopicName(value.to_string())),
+            "condition" => Ok(Target::Condition(value.to_string())),
+            // Registration tokens contain a colon, so anything else than
+            // a miscased prefix is a token.
+            _ if ["token", "topic", "condition"]
+                .iter()
+                .any(|reserved| prefix.eq_ignore_ascii_case(reserved)) =>
+            {
+                Err(TargetError::UnknownPrefix(prefix.to_string()))
+            }
+            _ => Ok(Target::Token(s.to_string())),
+        }
+    }
+}
+
+/// Maximum amount of topics in a condition.
+/// <https://firebase.google.com/docs/cloud-messaging/send-message#send-messages-to-topics>
+pub const MAX_CONDITION_TOPICS: usize = 5;
+
+/// Amount of `in topics` expressions in `condition`. The condition is
+/// not parsed, so this is only an approximation for invalid conditions.
+pub(crate) fn condition_topic_count(condition: &str) -> usize {
+    condition.matches("in topics").count()
+}
+
+/// Check that `name` matches FCM topic name pattern `[a-zA-Z0-9-_.~%]+`.
+pub(crate) fn is_valid_topic_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~' | '%'))
+}
@@ -0,0 +1,45 @@
+use serde_json::Value;
+
+use super::{AndroidConfig, ApnsConfig, FcmOptions, Message, Notification, Target, WebpushConfig};
+
+/// Fields which are shared by many messages, for example a notification
+/// which is sent to many users with personalized `data`.
+///
+/// [MessageTemplate::render] moves target and data into the message, so
+/// only the shared fields are cloned for each message.
+#[derive(Debug, Default, Clone)]
+pub struct MessageTemplate {
+    pub notification: Option<Notification>,
+    pub android: Option<AndroidConfig>,
+    pub webpush: Option<WebpushConfig>,
+    pub apns: Option<ApnsConfig>,
+    pub fcm_options: Option<FcmOptions>,
+}
+
+impl MessageTemplate {
+    /// Create a message with the shared fields, `target` and `data`.
+    pub fn render(&self, target: Target, data: Value) -> Message {
+        Message {
+            data: Some(data),
+            notification: self.notification.clone(),
+            android: self.android.clone(),
+            webpush: self.webpush.clone(),
+            apns: self.apns.clone(),
+            fcm_options: self.fcm_options.clone(),
+            target,
+        }
+    }
+}
+
+impl From<Message> for MessageTemplate {
+    /// Use the fields of `message` except `target` and `data`.
+    fn from(message: Message) -> Self {
+        MessageTemplate {
+            notification: message.notification,
+            android: message.android,
+            webpush: message.webpush,
+            apns: message.apns,
+            fcm_options: message.fcm_options,
+        }
+    }
+}
@@ -1,5 +1,14 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::Duration;
+
 use crate::{
-    message::{Message, Target},
+    message::{
+        AnalyticsLabel, AnalyticsLabelError, AndroidConfig, AndroidFcmOptions, AndroidMessagePriority,
+        AndroidNotification, AndroidNotificationError, ApnsConfig, ApnsError, ApnsFcmOptions, DataError, Lint, Message,
+        MessageError, MessageTemplate, MessageWrapper, NormalizeOptions, Platform, ResolvedNotification, SizeReport,
+        Target, TargetError, TopicCondition, TtlParseError, WebpushConfig,
+    },
     notification::Notification,
 };
 use serde_json::json;
@@ -180,3 +189,1225 @@ fn should_set_notifications() {
 
     assert!(msg.notification.is_some());
 }
+
+#[test]
+fn should_build_two_topic_condition() {
+    let condition = TopicCondition::in_topic("TopicA").and(TopicCondition::in_topic("TopicB").not());
+
+    let target = Target::try_from(condition).unwrap();
+
+    assert_eq!(
+        Target::Condition("'TopicA' in topics && !('TopicB' in topics)".to_string()),
+        target
+    );
+}
+
+#[test]
+fn should_build_five_topic_condition() {
+    let condition = TopicCondition::in_topic("TopicA")
+        .and(TopicCondition::in_topic("TopicB").or(TopicCondition::in_topic("TopicC")))
+        .or(TopicCondition::in_topic("TopicD").and(TopicCondition::in_topic("TopicE")));
+
+    let target = Target::try_from(condition).unwrap();
+
+    assert_eq!(
+        Target::Condition(
+            "('TopicA' in topics && ('TopicB' in topics || 'TopicC' in topics)) || \
+             ('TopicD' in topics && 'TopicE' in topics)"
+                .to_string()
+        ),
+        target
+    );
+}
+
+#[test]
+fn should_reject_six_topic_condition() {
+    let condition = TopicCondition::in_topic("TopicA")
+        .or(TopicCondition::in_topic("TopicB"))
+        .or(TopicCondition::in_topic("TopicC"))
+        .or(TopicCondition::in_topic("TopicD"))
+        .or(TopicCondition::in_topic("TopicE"))
+        .or(TopicCondition::in_topic("TopicF"));
+
+    assert_eq!(Err(TargetError::TooManyTopics(6)), Target::try_from(condition));
+}
+
+#[test]
+fn should_negate_condition_with_not_operator() {
+    assert_eq!(
+        TopicCondition::in_topic("TopicA").not(),
+        !TopicCondition::in_topic("TopicA")
+    );
+}
+
+#[test]
+fn should_reject_condition_without_operands() {
+    for condition in [
+        TopicCondition::And(vec![]),
+        TopicCondition::Or(vec![]),
+        TopicCondition::Or(vec![TopicCondition::And(vec![]), TopicCondition::in_topic("TopicA")]),
+    ] {
+        assert_eq!(Err(TargetError::EmptyCondition), Target::try_from(condition));
+    }
+}
+
+#[test]
+fn should_reject_invalid_topic_name_in_condition() {
+    let condition = TopicCondition::in_topic("Topic A");
+
+    assert_eq!(
+        Err(TargetError::InvalidTopicName("Topic A".to_string())),
+        Target::try_from(condition)
+    );
+}
+
+#[test]
+fn should_parse_prefixed_target() {
+    assert_eq!(Ok(Target::Token("abc123".to_string())), "token:abc123".parse());
+    assert_eq!(Ok(Target::Topic("news".to_string())), "topic:news".parse());
+    assert_eq!(
+        Ok(Target::Condition("'a' in topics && 'b' in topics".to_string())),
+        "condition:'a' in topics && 'b' in topics".parse()
+    );
+}
+
+#[test]
+fn should_parse_unprefixed_target_as_token() {
+    assert_eq!(Ok(Target::Token("abc123".to_string())), "abc123".parse());
+    assert_eq!(
+        Ok(Target::Token("dGVzdA-_x1:APA91bH_x-y".to_string())),
+        "dGVzdA-_x1:APA91bH_x-y".parse()
+    );
+    assert_eq!(Ok(Target::Token("abc:def".to_string())), "abc:def".parse());
+    assert_eq!(Ok(Target::Token(":abc".to_string())), ":abc".parse());
+}
+
+#[test]
+fn should_reject_miscased_target_prefix() {
+    for prefix in ["Token", "TOPIC", "Condition"] {
+        assert_eq!(
+            Err(TargetError::UnknownPrefix(prefix.to_string())),
+            format!("{}:news", prefix).parse::<Target>()
+        );
+    }
+    assert_eq!(
+        Err(TargetError::InvalidTopicName("my news".to_string())),
+        "topic:my news".parse::<Target>()
+    );
+}
+
+#[test]
+fn should_display_target_with_prefix() {
+    for target in [
+        Target::Token("abc123".to_string()),
+        Target::Topic("news".to_string()),
+        Target::Condition("'a' in topics".to_string()),
+    ] {
+        assert_eq!(Ok(target.clone()), target.to_string().parse());
+    }
+    assert_eq!("topic:news", Target::Topic("news".to_string()).to_string());
+}
+
+#[test]
+fn should_set_data_from_struct() {
+    #[derive(serde::Serialize)]
+    struct Payload {
+        kind: &'static str,
+        count: u32,
+        urgent: bool,
+        note: Option<String>,
+    }
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({}));
+
+    msg.set_data_from(&Payload {
+        kind: "order",
+        count: 3,
+        urgent: true,
+        note: None,
+    })
+    .unwrap();
+
+    assert_eq!(
+        Some(json!({ "kind": "order", "count": "3", "urgent": "true" })),
+        msg.data
+    );
+}
+
+#[test]
+fn should_reject_data_which_is_not_flat_object() {
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "key": "value" }));
+
+    assert_eq!(Err(DataError::NotObject), msg.set_data_from(&vec!["a", "b"]));
+    assert_eq!(
+        Err(DataError::NestedValue("nested".to_string())),
+        msg.set_data_from(&json!({ "key": "value", "nested": { "a": 1 } }))
+    );
+    assert_eq!(Some(json!({ "key": "value" })), msg.data);
+}
+
+#[test]
+fn should_stringify_data_values() {
+    let msg = Message {
+        target: Target::Token("token".to_string()),
+        data: Some(json!({ "count": 5, "flag": true, "name": "foo", "nested": { "a": 1 } })),
+        notification: None,
+        android: None,
+        webpush: None,
+        apns: None,
+        fcm_options: None,
+    }
+    .stringify_data();
+
+    let payload = serde_json::to_string(&msg).unwrap();
+
+    let expected_payload = json!({
+        "data": {
+            "count": "5",
+            "flag": "true",
+            "name": "foo",
+            "nested": "{\"a\":1}",
+        },
+        "token": "token"
+    })
+    .to_string();
+
+    assert_eq!(expected_payload, payload);
+}
+
+#[test]
+fn should_lint_direct_boot_ok_with_notification() {
+    let msg = Message {
+        target: Target::Token("token".to_string()),
+        data: None,
+        notification: None,
+        android: Some(AndroidConfig {
+            notification: Some(AndroidNotification {
+                title: Some("title".to_string()),
+                ..Default::default()
+            }),
+            ..AndroidConfig::default().direct_boot_ok(true)
+        }),
+        webpush: None,
+        apns: None,
+        fcm_options: None,
+    };
+
+    assert_eq!(vec![Lint::DirectBootOkWithNotification], msg.lint());
+}
+
+#[test]
+fn should_lint_click_action() {
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    msg.android = Some(AndroidConfig {
+        notification: Some(AndroidNotification {
+            click_action: Some("OPEN_ACTIVITY".to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    msg.webpush = Some(WebpushConfig {
+        notification: Some(json!({ "title": "title", "click_action": "https://example.com" })),
+        ..Default::default()
+    });
+
+    assert_eq!(vec![Lint::AndroidClickAction, Lint::WebpushClickAction], msg.lint());
+}
+
+#[test]
+fn should_not_lint_direct_boot_ok_data_message() {
+    let msg = Message {
+        target: Target::Token("token".to_string()),
+        data: Some(json!({ "key": "value" })),
+        notification: None,
+        android: Some(AndroidConfig::default().direct_boot_ok(true)),
+        webpush: None,
+        apns: None,
+        fcm_options: None,
+    };
+
+    assert!(msg.lint().is_empty());
+
+    let payload = serde_json::to_value(&msg).unwrap();
+    assert_eq!(json!({ "direct_boot_ok": true }), payload["android"]);
+}
+
+#[test]
+fn should_create_direct_boot_data_message_config() {
+    let android = AndroidConfig::direct_boot_data_message(json!({ "key": "value" }));
+
+    assert!(android.notification.is_none());
+    assert_eq!(
+        json!({
+            "priority": "HIGH",
+            "data": { "key": "value" },
+            "direct_boot_ok": true,
+        }),
+        serde_json::to_value(&android).unwrap()
+    );
+}
+
+#[test]
+fn should_map_high_android_priority_to_apns_priority() {
+    let msg = Message {
+        target: Target::Token("token".to_string()),
+        data: None,
+        notification: None,
+        android: Some(AndroidConfig {
+            priority: Some(AndroidMessagePriority::High),
+            ..Default::default()
+        }),
+        webpush: None,
+        apns: None,
+        fcm_options: None,
+    }
+    .normalize_priority();
+
+    let payload = serde_json::to_value(&msg).unwrap();
+
+    assert_eq!(json!({ "apns-priority": "10" }), payload["apns"]["headers"]);
+}
+
+#[test]
+fn should_keep_existing_apns_priority() {
+    let msg = Message {
+        target: Target::Token("token".to_string()),
+        data: None,
+        notification: None,
+        android: Some(AndroidConfig {
+            priority: Some(AndroidMessagePriority::Normal),
+            ..Default::default()
+        }),
+        webpush: None,
+        apns: Some(ApnsConfig {
+            headers: Some(json!({ "apns-priority": "10", "apns-topic": "topic" })),
+            ..Default::default()
+        }),
+        fcm_options: None,
+    }
+    .normalize_priority();
+
+    let apns = msg.apns.unwrap();
+    assert_eq!(Some("10"), apns.header("apns-priority"));
+    assert_eq!(Some("topic"), apns.header("apns-topic"));
+}
+
+#[test]
+fn should_render_pretty_json_of_request_body() {
+    let msg = Message {
+        target: Target::Token("token".to_string()),
+        data: Some(json!({ "foo": "bar" })),
+        notification: Some(Notification {
+            title: Some("title".to_string()),
+            body: None,
+            image: None,
+        }),
+        android: None,
+        webpush: None,
+        apns: None,
+        fcm_options: None,
+    };
+
+    let pretty = msg.to_pretty_json();
+    let compact = serde_json::to_string(&MessageWrapper::new(&msg, false)).unwrap();
+
+    assert!(pretty.contains('\n'));
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&compact).unwrap(),
+        serde_json::from_str::<serde_json::Value>(&pretty).unwrap()
+    );
+}
+
+fn message_with_platform_data(
+    android_data: Option<serde_json::Value>,
+    webpush_data: Option<serde_json::Value>,
+) -> Message {
+    Message {
+        target: Target::Token("token".to_string()),
+        data: Some(json!({ "a": "top", "b": "top" })),
+        notification: None,
+        android: Some(AndroidConfig {
+            data: android_data,
+            ..Default::default()
+        }),
+        webpush: Some(WebpushConfig {
+            data: webpush_data,
+            ..Default::default()
+        }),
+        apns: None,
+        fcm_options: None,
+    }
+}
+
+#[test]
+fn should_override_top_level_data_with_android_data() {
+    let msg = message_with_platform_data(Some(json!({ "b": "android", "c": "android" })), None);
+
+    let expected = json!({ "a": "top", "b": "android", "c": "android" });
+    assert_eq!(expected, serde_json::Value::Object(msg.effective_android_data()));
+}
+
+#[test]
+fn should_use_top_level_data_without_android_data() {
+    let mut msg = message_with_platform_data(None, None);
+    assert_eq!(msg.data, Some(serde_json::Value::Object(msg.effective_android_data())));
+
+    msg.android = None;
+    assert_eq!(msg.data, Some(serde_json::Value::Object(msg.effective_android_data())));
+}
+
+#[test]
+fn should_override_top_level_data_with_webpush_data() {
+    let msg = message_with_platform_data(None, Some(json!({ "b": "webpush", "c": "webpush" })));
+
+    let expected = json!({ "a": "top", "b": "webpush", "c": "webpush" });
+    assert_eq!(expected, serde_json::Value::Object(msg.effective_webpush_data()));
+}
+
+#[test]
+fn should_union_top_level_data_and_webpush_data() {
+    let mut msg = message_with_platform_data(None, Some(json!({ "c": "webpush" })));
+    msg.data = None;
+    assert_eq!(
+        json!({ "c": "webpush" }),
+        serde_json::Value::Object(msg.effective_webpush_data())
+    );
+
+    msg.data = Some(json!({ "a": "top" }));
+    assert_eq!(
+        json!({ "a": "top", "c": "webpush" }),
+        serde_json::Value::Object(msg.effective_webpush_data())
+    );
+}
+
+#[test]
+fn should_serialize_data_only_message_without_notification() {
+    let msg = Message::data_only(Target::Topic("sync".to_string()), json!({ "sync": "true" }));
+
+    assert!(msg.is_data_only());
+    let payload = serde_json::to_value(&msg).unwrap();
+    assert_eq!(json!({ "data": { "sync": "true" }, "topic": "sync" }), payload);
+}
+
+#[test]
+fn should_not_be_data_only_with_platform_notification() {
+    let mut msg = Message::data_only(Target::Topic("sync".to_string()), json!({ "sync": "true" }));
+    msg.android = Some(AndroidConfig {
+        notification: Some(AndroidNotification::default()),
+        ..Default::default()
+    });
+    assert!(!msg.is_data_only());
+
+    msg.android = None;
+    msg.apns = Some(ApnsConfig {
+        payload: Some(json!({ "aps": { "alert": "Hello" } })),
+        ..Default::default()
+    });
+    assert!(!msg.is_data_only());
+
+    msg.apns = Some(ApnsConfig {
+        payload: Some(json!({ "aps": { "content-available": 1 } })),
+        ..Default::default()
+    });
+    assert!(msg.is_data_only());
+}
+
+#[test]
+fn should_classify_notification_data_and_mixed_messages() {
+    let classify = |msg: &Message| (msg.is_notification_message(), msg.is_data_message(), msg.is_mixed());
+    let notification = Notification {
+        title: Some("Hello".to_string()),
+        ..Default::default()
+    };
+
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    assert_eq!((false, true, false), classify(&msg));
+
+    msg.notification = Some(notification.clone());
+    assert_eq!((false, false, true), classify(&msg));
+
+    msg.data = None;
+    assert_eq!((true, false, false), classify(&msg));
+
+    msg.android = Some(AndroidConfig::default().with_data(json!({ "id": "1" })));
+    assert_eq!((false, false, true), classify(&msg));
+
+    msg.notification = None;
+    assert_eq!((false, true, false), classify(&msg));
+
+    msg.android = None;
+    msg.apns = Some(ApnsConfig::default().with_payload(json!({ "aps": { "alert": "Hello" } })));
+    assert_eq!((true, false, false), classify(&msg));
+
+    msg.apns = None;
+    msg.webpush = Some(WebpushConfig::default().with_data(json!({ "id": "1" })));
+    assert_eq!((false, true, false), classify(&msg));
+
+    msg.webpush = None;
+    assert_eq!((false, false, false), classify(&msg));
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn should_compress_data_field_reversibly() {
+    use base64::Engine;
+    use std::io::Read;
+
+    let large_value = "All work and no play makes Jack a dull boy. ".repeat(200);
+    let mut msg = Message::data_only(
+        Target::Token("token".to_string()),
+        json!({ "report": large_value, "id": "1" }),
+    );
+
+    assert!(msg.compress_data_field("report"));
+    assert!(!msg.compress_data_field("missing"));
+
+    let data = msg.data.as_ref().unwrap();
+    assert_eq!(json!("gzip+base64"), data["report_encoding"]);
+    assert_eq!(json!("1"), data["id"]);
+    let encoded = data["report"].as_str().unwrap();
+    assert!(encoded.len() < large_value.len() / 10);
+
+    let compressed = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+    let mut decompressed = String::new();
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    assert_eq!(large_value, decompressed);
+}
+
+#[test]
+fn should_build_android_config_fluently() {
+    let android = AndroidConfig::default()
+        .with_collapse_key("key")
+        .with_priority(AndroidMessagePriority::High)
+        .with_ttl(Duration::from_secs(60))
+        .with_restricted_package_name("com.example")
+        .with_data(json!({ "key": "value" }));
+
+    assert_eq!(
+        json!({
+            "collapse_key": "key",
+            "priority": "HIGH",
+            "ttl": "60s",
+            "restricted_package_name": "com.example",
+            "data": { "key": "value" },
+        }),
+        serde_json::to_value(&android).unwrap()
+    );
+}
+
+#[test]
+fn should_build_apns_config_fluently() {
+    let apns = ApnsConfig::default()
+        .with_header("apns-priority", "10")
+        .with_payload(json!({ "aps": { "badge": 1 } }));
+
+    assert_eq!(
+        json!({
+            "headers": { "apns-priority": "10" },
+            "payload": { "aps": { "badge": 1 } },
+        }),
+        serde_json::to_value(&apns).unwrap()
+    );
+}
+
+#[test]
+fn should_build_webpush_config_fluently() {
+    let webpush = WebpushConfig::default()
+        .with_headers(json!({ "TTL": "60" }))
+        .with_header("Urgency", "high")
+        .with_data(json!({ "key": "value" }))
+        .with_notification(json!({ "title": "Hello" }));
+
+    assert_eq!(
+        json!({
+            "headers": { "TTL": "60", "Urgency": "high" },
+            "data": { "key": "value" },
+            "notification": { "title": "Hello" },
+        }),
+        serde_json::to_value(&webpush).unwrap()
+    );
+}
+
+#[test]
+fn should_parse_android_ttl_duration() {
+    let ttl_duration = |ttl: &str| {
+        AndroidConfig {
+            ttl: Some(ttl.to_string()),
+            ..Default::default()
+        }
+        .ttl_duration()
+    };
+
+    assert_eq!(Some(Ok(Duration::from_secs(10))), ttl_duration("10s"));
+    assert_eq!(Some(Ok(Duration::from_millis(500))), ttl_duration("0.5s"));
+    assert_eq!(Some(Ok(Duration::new(3, 1))), ttl_duration("3.000000001s"));
+    assert_eq!(None, AndroidConfig::default().ttl_duration());
+}
+
+#[test]
+fn should_reject_invalid_android_ttl() {
+    for ttl in ["10", "s", ".5s", "1.s", "-1s", "1.0000000001s", "1m"] {
+        let android = AndroidConfig {
+            ttl: Some(ttl.to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(Some(Err(TtlParseError(ttl.to_string()))), android.ttl_duration());
+    }
+}
+
+#[test]
+fn should_parse_formatted_android_ttl() {
+    let mut android = AndroidConfig::default();
+    android.set_ttl_duration(Duration::from_millis(3500));
+
+    assert_eq!(Some(Ok(Duration::from_millis(3500))), android.ttl_duration());
+}
+
+#[test]
+fn should_set_alert_apns_push_type() {
+    let mut apns = ApnsConfig {
+        payload: Some(json!({ "aps": { "alert": { "title": "Hello" }, "content-available": 1 } })),
+        ..Default::default()
+    };
+
+    apns.auto_push_type();
+
+    assert_eq!(Some("alert"), apns.header("apns-push-type"));
+}
+
+#[test]
+fn should_set_background_apns_push_type() {
+    let mut apns = ApnsConfig {
+        payload: Some(json!({ "aps": { "content-available": 1 } })),
+        ..Default::default()
+    };
+
+    apns.auto_push_type();
+
+    assert_eq!(Some("background"), apns.header("apns-push-type"));
+}
+
+#[test]
+fn should_keep_existing_apns_push_type() {
+    let mut apns = ApnsConfig {
+        headers: Some(json!({ "apns-push-type": "voip" })),
+        payload: Some(json!({ "aps": { "content-available": 1 } })),
+        ..Default::default()
+    };
+
+    apns.auto_push_type();
+
+    assert_eq!(Some("voip"), apns.header("apns-push-type"));
+}
+
+#[test]
+fn should_normalize_priority_and_apns_push_type() {
+    let msg = Message {
+        data: None,
+        notification: None,
+        target: Target::Token("token".to_string()),
+        android: Some(AndroidConfig {
+            priority: Some(AndroidMessagePriority::Normal),
+            ..Default::default()
+        }),
+        webpush: None,
+        apns: Some(ApnsConfig {
+            payload: Some(json!({ "aps": { "content-available": 1 } })),
+            ..Default::default()
+        }),
+        fcm_options: None,
+    };
+
+    let msg = msg.normalize();
+
+    assert_eq!(
+        json!({ "apns-priority": "5", "apns-push-type": "background" }),
+        msg.apns.unwrap().headers.unwrap()
+    );
+}
+
+#[test]
+fn should_accept_valid_data_keys() {
+    let msg = Message::data_only(
+        Target::Token("token".to_string()),
+        json!({ "sync": "true", "from_user": "äö", "message": "ok" }),
+    );
+
+    assert_eq!(Ok(()), msg.validate());
+}
+
+#[test]
+fn should_reject_reserved_data_keys() {
+    for key in [
+        "google.sent_time",
+        "gcm.notification.title",
+        "googleKey",
+        "from",
+        "message_type",
+    ] {
+        let msg = Message::data_only(Target::Token("token".to_string()), json!({ key: "value" }));
+
+        assert_eq!(Err(MessageError::ReservedDataKey(key.to_string())), msg.validate());
+    }
+}
+
+#[test]
+fn should_reject_reserved_android_data_key() {
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "sync": "true" }));
+    msg.android = Some(AndroidConfig {
+        data: Some(json!({ "google.c.a.e": "1" })),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        Err(MessageError::ReservedDataKey("google.c.a.e".to_string())),
+        msg.validate()
+    );
+}
+
+#[test]
+fn should_accept_replacement_character_in_data_value() {
+    let msg = Message::data_only(Target::Token("token".to_string()), json!({ "name": "caf\u{FFFD}" }));
+
+    assert_eq!(Ok(()), msg.validate());
+}
+
+#[test]
+fn should_reject_message_with_only_target() {
+    let msg = Message {
+        data: None,
+        notification: None,
+        target: Target::Topic("news".to_string()),
+        android: None,
+        webpush: None,
+        apns: None,
+        fcm_options: None,
+    };
+
+    assert_eq!(Err(MessageError::Empty), msg.validate());
+}
+
+#[test]
+fn should_construct_valid_analytics_labels() {
+    let max_length_label = "a".repeat(50);
+    for label in ["campaign-1", "a", "A.b_c~d%20", max_length_label.as_str()] {
+        assert_eq!(label, AnalyticsLabel::try_from(label).unwrap().as_str());
+    }
+}
+
+#[test]
+fn should_reject_invalid_analytics_labels() {
+    let too_long_label = "a".repeat(51);
+    for label in ["", "campaign 1", "kampanja-ä", "a/b", too_long_label.as_str()] {
+        assert_eq!(
+            Err(AnalyticsLabelError(label.to_string())),
+            AnalyticsLabel::try_from(label.to_string())
+        );
+    }
+}
+
+#[test]
+fn should_serialize_analytics_label_as_string() {
+    let fcm_options = ApnsFcmOptions {
+        analytics_label: Some(AnalyticsLabel::try_from("campaign-1").unwrap()),
+        image: None,
+    };
+
+    assert_eq!(
+        json!({ "analytics_label": "campaign-1" }),
+        serde_json::to_value(&fcm_options).unwrap()
+    );
+}
+
+#[cfg(feature = "legacy-json")]
+#[test]
+fn should_convert_token_message_to_legacy_json() {
+    let msg = Message {
+        data: Some(json!({ "id": "1" })),
+        notification: Some(Notification {
+            title: Some("Title".to_string()),
+            body: Some("Body".to_string()),
+            image: None,
+        }),
+        target: Target::Token("token".to_string()),
+        android: None,
+        webpush: None,
+        apns: None,
+        fcm_options: None,
+    };
+
+    let expected_json = json!({
+        "to": "token",
+        "notification": {
+            "title": "Title",
+            "body": "Body",
+        },
+        "data": { "id": "1" },
+    });
+    assert_eq!(Ok(expected_json), msg.to_legacy_json());
+
+    let msg = Message::data_only(Target::Topic("news".to_string()), json!({ "id": "1" }));
+    assert_eq!(json!("/topics/news"), msg.to_legacy_json().unwrap()["to"]);
+}
+
+#[cfg(feature = "legacy-json")]
+#[test]
+fn should_not_convert_unsupported_message_to_legacy_json() {
+    use crate::message::LegacyConversionError;
+
+    let msg = Message::data_only(Target::Condition("'news' in topics".to_string()), json!({ "id": "1" }));
+    assert_eq!(Err(LegacyConversionError::ConditionTarget), msg.to_legacy_json());
+
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    msg.android = Some(AndroidConfig::default());
+    assert_eq!(
+        Err(LegacyConversionError::UnsupportedField("android")),
+        msg.to_legacy_json()
+    );
+}
+
+#[test]
+fn should_deserialize_android_config_from_snake_case_and_camel_case() {
+    for android_json in [
+        json!({
+            "collapse_key": "key",
+            "restricted_package_name": "com.example.app",
+            "direct_boot_ok": true,
+            "fcm_options": { "analytics_label": "label" },
+            "notification": { "channel_id": "channel" },
+        }),
+        json!({
+            "collapseKey": "key",
+            "restrictedPackageName": "com.example.app",
+            "directBootOk": true,
+            "fcmOptions": { "analyticsLabel": "label" },
+            "notification": { "channelId": "channel" },
+        }),
+    ] {
+        let android: AndroidConfig = serde_json::from_value(android_json).unwrap();
+
+        assert_eq!(Some("key"), android.collapse_key.as_deref());
+        assert_eq!(Some("com.example.app"), android.restricted_package_name.as_deref());
+        assert_eq!(Some(true), android.direct_boot_ok);
+        assert_eq!("label", android.fcm_options.unwrap().analytics_label.as_str());
+        assert_eq!(Some("channel"), android.notification.unwrap().channel_id.as_deref());
+    }
+}
+
+#[test]
+fn should_deserialize_android_message_priority_ignoring_case() {
+    for priority in ["high", "HIGH", "High"] {
+        let priority: AndroidMessagePriority = serde_json::from_value(json!(priority)).unwrap();
+        assert!(matches!(priority, AndroidMessagePriority::High));
+    }
+    for priority in ["normal", "NORMAL", "Normal"] {
+        let priority: AndroidMessagePriority = serde_json::from_value(json!(priority)).unwrap();
+        assert!(matches!(priority, AndroidMessagePriority::Normal));
+    }
+    assert!(serde_json::from_value::<AndroidMessagePriority>(json!("urgent")).is_err());
+
+    let android: AndroidConfig = serde_json::from_value(json!({ "priority": "high" })).unwrap();
+    assert_eq!(json!({ "priority": "HIGH" }), serde_json::to_value(&android).unwrap());
+}
+
+#[test]
+fn should_serialize_android_config_with_snake_case_names() {
+    let android: AndroidConfig = serde_json::from_value(json!({
+        "collapseKey": "key",
+        "restrictedPackageName": "com.example.app",
+        "directBootOk": true,
+        "fcmOptions": { "analyticsLabel": "label" },
+        "notification": { "channelId": "channel" },
+    }))
+    .unwrap();
+
+    assert_eq!(
+        json!({
+            "collapse_key": "key",
+            "restricted_package_name": "com.example.app",
+            "direct_boot_ok": true,
+            "fcm_options": { "analytics_label": "label" },
+            "notification": { "channel_id": "channel" },
+        }),
+        serde_json::to_value(&android).unwrap()
+    );
+}
+
+#[test]
+fn should_not_deserialize_invalid_analytics_label() {
+    let result = serde_json::from_value::<ApnsFcmOptions>(json!({ "analyticsLabel": "not valid" }));
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn should_validate_android_notification_count() {
+    let notification = |count| AndroidNotification {
+        notification_count: Some(count),
+        ..Default::default()
+    };
+
+    assert_eq!(Ok(()), notification(0).validate());
+    assert_eq!(Ok(()), notification(5).validate());
+    assert_eq!(
+        Err(AndroidNotificationError::NegativeNotificationCount(-1)),
+        notification(-1).validate()
+    );
+
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    msg.android = Some(AndroidConfig {
+        notification: Some(notification(-1)),
+        ..Default::default()
+    });
+    assert_eq!(
+        Err(MessageError::AndroidNotification(
+            AndroidNotificationError::NegativeNotificationCount(-1)
+        )),
+        msg.validate()
+    );
+}
+
+#[test]
+fn should_reject_condition_with_too_many_topics() {
+    let condition = (1..=6)
+        .map(|i| format!("'topic{}' in topics", i))
+        .collect::<Vec<_>>()
+        .join(" || ");
+    let msg = Message::data_only(Target::Condition(condition), json!({ "id": "1" }));
+
+    assert_eq!(Err(MessageError::Target(TargetError::TooManyTopics(6))), msg.validate());
+}
+
+#[test]
+fn should_set_ttl_for_all_platforms() {
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    let before = chrono::Utc::now().timestamp();
+
+    msg.set_ttl(Duration::from_secs(3600));
+
+    let after = chrono::Utc::now().timestamp();
+    assert_eq!(Some("3600s"), msg.android.unwrap().ttl.as_deref());
+    assert_eq!(Some("3600"), msg.webpush.unwrap().header("TTL"));
+    let expiration: i64 = msg.apns.unwrap().header("apns-expiration").unwrap().parse().unwrap();
+    assert!((before + 3600..=after + 3600).contains(&expiration));
+}
+
+#[test]
+fn should_set_apns_expiration_relative_to_given_time() {
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    let now = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+
+    msg.set_ttl_at(now, Duration::from_secs(60));
+
+    assert_eq!(Some("1704067260"), msg.apns.unwrap().header("apns-expiration"));
+}
+
+#[test]
+fn should_validate_apns_silent_and_alert_payloads() {
+    let apns = |payload| ApnsConfig {
+        payload: Some(payload),
+        ..Default::default()
+    };
+
+    assert_eq!(Ok(()), apns(json!({ "aps": { "content-available": 1 } })).validate());
+    assert_eq!(Ok(()), apns(json!({ "aps": { "alert": "Hello" } })).validate());
+    assert_eq!(
+        Err(ApnsError::SilentWithAlert),
+        apns(json!({ "aps": { "alert": "Hello", "content-available": 1 } })).validate()
+    );
+
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    msg.apns = Some(apns(json!({ "aps": { "alert": "Hello", "content-available": 1 } })));
+    assert_eq!(Err(MessageError::Apns(ApnsError::SilentWithAlert)), msg.validate());
+}
+
+#[test]
+fn should_render_messages_from_template() {
+    let template = MessageTemplate {
+        notification: Some(Notification {
+            title: Some("Sale".to_string()),
+            ..Default::default()
+        }),
+        android: Some(AndroidConfig {
+            collapse_key: Some("sale".to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let first = template.render(Target::Token("token-1".to_string()), json!({ "name": "Alice" }));
+    let second = template.render(Target::Token("token-2".to_string()), json!({ "name": "Bob" }));
+
+    let first = serde_json::to_value(&first).unwrap();
+    let second = serde_json::to_value(&second).unwrap();
+    assert_eq!(
+        json!({
+            "data": { "name": "Alice" },
+            "notification": { "title": "Sale" },
+            "android": { "collapse_key": "sale" },
+            "token": "token-1",
+        }),
+        first
+    );
+    assert_eq!(json!({ "name": "Bob" }), second["data"]);
+    assert_eq!(json!("token-2"), second["token"]);
+    assert_eq!(first["notification"], second["notification"]);
+}
+
+#[test]
+fn should_serialize_fcm_options_image_fields() {
+    let apns_fcm_options = ApnsFcmOptions {
+        analytics_label: None,
+        image: Some("https://example.com/image.png".to_string()),
+    };
+    let android_fcm_options = AndroidFcmOptions {
+        analytics_label: AnalyticsLabel::try_from("campaign-1").unwrap(),
+    };
+
+    assert_eq!(
+        json!({ "image": "https://example.com/image.png" }),
+        serde_json::to_value(&apns_fcm_options).unwrap()
+    );
+    assert_eq!(json!({}), serde_json::to_value(ApnsFcmOptions::default()).unwrap());
+    assert_eq!(
+        json!({ "analytics_label": "campaign-1" }),
+        serde_json::to_value(&android_fcm_options).unwrap()
+    );
+}
+
+#[test]
+fn should_substitute_condition_placeholders() {
+    let substitutions = HashMap::from([
+        ("locale".to_string(), "fi".to_string()),
+        ("segment".to_string(), "premium".to_string()),
+    ]);
+
+    assert_eq!(
+        Ok("'fi_news' in topics && 'premium' in topics".to_string()),
+        TopicCondition::with_substitutions("'{locale}_news' in topics && '{segment}' in topics", &substitutions)
+    );
+    assert_eq!(
+        Err(TargetError::UnknownPlaceholder("country".to_string())),
+        TopicCondition::with_substitutions("'{country}_news' in topics", &substitutions)
+    );
+
+    let substitutions = HashMap::from([("locale".to_string(), "fi' in topics || 'all".to_string())]);
+    assert_eq!(
+        Err(TargetError::InvalidTopicName("fi' in topics || 'all".to_string())),
+        TopicCondition::with_substitutions("'{locale}_news' in topics", &substitutions)
+    );
+}
+
+#[test]
+fn should_resolve_android_notification_overriding_common_title() {
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    msg.notification = Some(Notification {
+        title: Some("Common title".to_string()),
+        body: Some("Common body".to_string()),
+        image: None,
+    });
+    msg.android = Some(AndroidConfig {
+        notification: Some(AndroidNotification {
+            title: Some("Android title".to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        ResolvedNotification {
+            title: Some("Android title".to_string()),
+            body: Some("Common body".to_string()),
+            image: None,
+        },
+        msg.resolve_notification(Platform::Android)
+    );
+}
+
+#[test]
+fn should_resolve_apns_notification_falling_back_to_common_title() {
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    msg.notification = Some(Notification {
+        title: Some("Common title".to_string()),
+        body: Some("Common body".to_string()),
+        image: None,
+    });
+    msg.apns = Some(ApnsConfig {
+        payload: Some(json!({ "aps": { "alert": "APNs body" } })),
+        fcm_options: Some(ApnsFcmOptions {
+            analytics_label: None,
+            image: Some("https://example.com/image.png".to_string()),
+        }),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        ResolvedNotification {
+            title: Some("Common title".to_string()),
+            body: Some("APNs body".to_string()),
+            image: Some("https://example.com/image.png".to_string()),
+        },
+        msg.resolve_notification(Platform::Apns)
+    );
+    assert_eq!(
+        Some("Common body".to_string()),
+        msg.resolve_notification(Platform::Webpush).body
+    );
+}
+
+#[test]
+fn should_report_section_sizes() {
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "key": "value" }));
+    msg.notification = Some(Notification {
+        title: Some("Hello".to_string()),
+        body: None,
+        image: None,
+    });
+    msg.apns = Some(ApnsConfig {
+        payload: Some(json!({ "aps": { "badge": 1 } })),
+        ..Default::default()
+    });
+
+    let report = msg.size_report();
+
+    // {"key":"value"}, {"title":"Hello"} and {"payload":{"aps":{"badge":1}}}
+    assert_eq!(
+        SizeReport {
+            data: 15,
+            notification: 17,
+            android: 0,
+            webpush: 0,
+            apns: 31,
+            total: serde_json::to_vec(&msg).unwrap().len(),
+        },
+        report
+    );
+    assert!(report.within_limits());
+
+    msg.apns = Some(ApnsConfig {
+        payload: Some(json!({ "aps": { "alert": "x".repeat(4096) } })),
+        ..Default::default()
+    });
+    assert!(msg.size_report().within_limits());
+
+    msg.data = Some(json!({ "key": "x".repeat(4096) }));
+    assert!(!msg.size_report().within_limits());
+}
+
+#[test]
+fn should_normalize_apns_expiration_from_android_ttl() {
+    #[derive(Debug)]
+    struct FixedClock(chrono::DateTime<chrono::Utc>);
+
+    impl crate::Clock for FixedClock {
+        fn now(&self) -> chrono::DateTime<chrono::Utc> {
+            self.0
+        }
+    }
+
+    let clock = FixedClock("2024-01-01T00:00:00Z".parse().unwrap());
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    msg.android = Some(AndroidConfig {
+        ttl: Some("3600s".to_string()),
+        ..Default::default()
+    });
+
+    let msg = msg.normalize_with_clock(&clock);
+
+    let expected = (clock.0.timestamp() + 3600).to_string();
+    assert_eq!(
+        Some(expected.as_str()),
+        msg.apns.as_ref().unwrap().header("apns-expiration")
+    );
+
+    let mut msg = msg;
+    msg.apns.as_mut().unwrap().set_header("apns-expiration", "0");
+    let msg = msg.normalize_with_clock(&clock);
+    assert_eq!(Some("0"), msg.apns.unwrap().header("apns-expiration"));
+}
+
+#[test]
+fn should_mirror_android_sound_to_apns_when_enabled() {
+    let options = NormalizeOptions {
+        mirror_android_sound: true,
+    };
+    let message = |notification: AndroidNotification| {
+        let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+        msg.android = Some(AndroidConfig {
+            notification: Some(notification),
+            ..Default::default()
+        });
+        msg
+    };
+    let ping = AndroidNotification {
+        sound: Some("ping.caf".to_string()),
+        ..Default::default()
+    };
+
+    let msg = message(ping.clone()).normalize_with_options(options);
+    assert_eq!(
+        json!({ "aps": { "sound": "ping.caf" } }),
+        msg.apns.unwrap().payload.unwrap()
+    );
+
+    let msg = message(AndroidNotification {
+        default_sound: Some(true),
+        ..Default::default()
+    })
+    .normalize_with_options(options);
+    assert_eq!(
+        json!({ "aps": { "sound": "default" } }),
+        msg.apns.unwrap().payload.unwrap()
+    );
+
+    let mut msg = message(ping.clone());
+    msg.apns = Some(ApnsConfig {
+        payload: Some(json!({ "aps": { "sound": "chime.caf" } })),
+        ..Default::default()
+    });
+    let msg = msg.normalize_with_options(options);
+    assert_eq!(
+        json!({ "aps": { "sound": "chime.caf" } }),
+        msg.apns.unwrap().payload.unwrap()
+    );
+
+    let msg = message(ping).normalize();
+    assert!(msg.apns.is_none());
+}
+
+#[test]
+fn should_merge_common_notification_into_platforms() {
+    let mut msg = Message::data_only(Target::Token("token".to_string()), json!({ "id": "1" }));
+    msg.notification = Some(Notification {
+        title: Some("Title".to_string()),
+        body: Some("Body".to_string()),
+        image: None,
+    });
+
+    let msg = msg.merge_common_notification_into_platforms();
+
+    let android_notification = msg.android.as_ref().unwrap().notification.as_ref().unwrap();
+    assert_eq!(Some("Title"), android_notification.title.as_deref());
+    assert_eq!(Some("Body"), android_notification.body.as_deref());
+    assert_eq!(
+        json!({ "aps": { "alert": { "title": "Title", "body": "Body" } } }),
+        msg.apns.as_ref().unwrap().payload.clone().unwrap()
+    );
+    for platform in [Platform::Android, Platform::Apns] {
+        assert_eq!(Some("Title".to_string()), msg.resolve_notification(platform).title);
+    }
+}
+
+#[test]
+fn should_fingerprint_message_without_target() {
+    let msg = Message::data_only(Target::Token("token".to_string()), json!({ "a": "1", "b": "2" }));
+    let other_target = Message::data_only(Target::Topic("topic".to_string()), json!({ "b": "2", "a": "1" }));
+    let other_data = Message::data_only(Target::Token("token".to_string()), json!({ "a": "1", "b": "3" }));
+
+    assert_eq!(msg.fingerprint(), other_target.fingerprint());
+    assert_ne!(msg.fingerprint(), other_data.fingerprint());
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Not;
+
+use super::target::{condition_topic_count, is_valid_topic_name, Target, TargetError, MAX_CONDITION_TOPICS};
+
+/// Builder for [Target::Condition] expressions.
+///
+/// ```rust
+/// use std::convert::TryFrom;
+/// use fcm::message::{Target, TopicCondition};
+///
+/// let condition = TopicCondition::in_topic("TopicA")
+///     .and(TopicCondition::in_topic("TopicB").or(TopicCondition::in_topic("TopicC").not()));
+///
+/// assert_eq!(
+///     Target::try_from(condition).unwrap(),
+///     Target::Condition("'TopicA' in topics && ('TopicB' in topics || !('TopicC' in topics))".to_string()),
+/// );
+/// ```
+///
+/// [TopicCondition::And] and [TopicCondition::Or] must have at least
+/// one operand.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TopicCondition {
+    Topic(String),
+    Not(Box<TopicCondition>),
+    And(Vec<TopicCondition>),
+    Or(Vec<TopicCondition>),
+}
+
+impl TopicCondition {
+    /// Condition which is true for devices subscribed to `topic`.
+    pub fn in_topic(topic: impl Into<String>) -> Self {
+        TopicCondition::Topic(topic.into())
+    }
+
+    /// Combine conditions with `&&`.
+    pub fn and(self, other: TopicCondition) -> Self {
+        match self {
+            TopicCondition::And(mut conditions) => {
+                conditions.push(other);
+                TopicCondition::And(conditions)
+            }
+            condition => TopicCondition::And(vec![condition, other]),
+        }
+    }
+
+    /// Combine conditions with `||`.
+    pub fn or(self, other: TopicCondition) -> Self {
+        match self {
+            TopicCondition::Or(mut conditions) => {
+                conditions.push(other);
+                TopicCondition::Or(conditions)
+            }
+            condition => TopicCondition::Or(vec![condition, other]),
+        }
+    }
+
+    /// Negate the condition with `!`. Same as [std::ops::Not], but does
+    /// not need the trait to be imported.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        TopicCondition::Not(Box::new(self))
+    }
+
+    /// Amount of topics in the condition. Every occurrence is counted.
+    pub fn topic_count(&self) -> usize {
+        match self {
+            TopicCondition::Topic(_) => 1,
+            TopicCondition::Not(condition) => condition.topic_count(),
+            TopicCondition::And(conditions) | TopicCondition::Or(conditions) => {
+                conditions.iter().map(TopicCondition::topic_count).sum()
+            }
+        }
+    }
+
+    /// Validate topic names and topic count and create the condition
+    /// string.
+    pub fn build(&self) -> Result<String, TargetError> {
+        let topic_count = self.topic_count();
+        if topic_count > MAX_CONDITION_TOPICS {
+            return Err(TargetError::TooManyTopics(topic_count));
+        }
+
+        let mut condition = String::new();
+        self.write_condition(&mut condition)?;
+        Ok(condition)
+    }
+
+    /// Replace `{name}` placeholders of condition string `template` with
+    /// values of `substitutions` and check topic count of the result.
+    ///
+    /// Values must contain only topic name characters, so that they
+    /// cannot change the structure of the condition.
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use fcm::message::TopicCondition;
+    ///
+    /// let substitutions = HashMap::from([("locale".to_string(), "fi".to_string())]);
+    /// let condition = TopicCondition::with_substitutions("'{locale}_news' in topics", &substitutions);
+    ///
+    /// assert_eq!(Ok("'fi_news' in topics".to_string()), condition);
+    /// ```
+    pub fn with_substitutions(template: &str, substitutions: &HashMap<String, String>) -> Result<String, TargetError> {
+        let mut condition = String::with_capacity(template.len());
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            if c != '{' {
+                condition.push(c);
+                continue;
+            }
+            let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+            let value = substitutions.get(&name).ok_or(TargetError::UnknownPlaceholder(name))?;
+            if !is_valid_topic_name(value) {
+                return Err(TargetError::InvalidTopicName(value.clone()));
+            }
+            condition.push_str(value);
+        }
+
+        let topic_count = condition_topic_count(&condition);
+        if topic_count > MAX_CONDITION_TOPICS {
+            return Err(TargetError::TooManyTopics(topic_count));
+        }
+        Ok(condition)
+    }
+
+    fn write_condition(&self, output: &mut String) -> Result<(), TargetError> {
+        match self {
+            TopicCondition::Topic(topic) => {
+                if !is_valid_topic_name(topic) {
+                    return Err(TargetError::InvalidTopicName(topic.clone()));
+                }
+                output.push('\'');
+                output.push_str(topic);
+                output.push_str("' in topics");
+            }
+            TopicCondition::Not(condition) => {
+                output.push_str("!(");
+                condition.write_condition(output)?;
+                output.push(')');
+            }
+            TopicCondition::And(conditions) => Self::write_operands(conditions, " && ", output)?,
+            TopicCondition::Or(conditions) => Self::write_operands(conditions, " || ", output)?,
+        }
+        Ok(())
+    }
+
+    fn write_operands(conditions: &[TopicCondition], operator: &str, output: &mut String) -> Result<(), TargetError> {
+        if conditions.is_empty() {
+            return Err(TargetError::EmptyCondition);
+        }
+        for (i, condition) in conditions.iter().enumerate() {
+            if i > 0 {
+                output.push_str(operator);
+            }
+            let needs_parentheses = matches!(condition, TopicCondition::And(_) | TopicCondition::Or(_));
+            if needs_parentheses {
+                output.push('(');
+            }
+            condition.write_condition(output)?;
+            if needs_parentheses {
+                output.push(')');
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Not for TopicCondition {
+    type Output = TopicCondition;
+
+    /// Negate the condition with `!`.
+    fn not(self) -> Self::Output {
+        TopicCondition::not(self)
+    }
+}
+
+impl TryFrom<TopicCondition> for Target {
+    type Error = TargetError;
+
+    fn try_from(condition: TopicCondition) -> Result<Self, Self::Error> {
+        condition.build().map(Target::Condition)
+    }
+}
+
+impl TryFrom<&TopicCondition> for Target {
+    type Error = TargetError;
+
+    fn try_from(condition: &TopicCondition) -> Result<Self, Self::Error> {
+        condition.build().map(Target::Condition)
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use super::{AndroidConfig, ApnsConfig, Message, WebpushConfig};
+use crate::{Clock, SystemClock};
+
+/// APNs request header for the time after which the notification is no
+/// longer delivered, as UNIX epoch seconds.
+pub const APNS_EXPIRATION_HEADER: &str = "apns-expiration";
+
+/// Web push request header for how long the push service keeps the
+/// message, in seconds.
+pub const WEBPUSH_TTL_HEADER: &str = "TTL";
+
+impl Message {
+    /// Set how long FCM and the platform push services try to deliver
+    /// the message. Missing platform configs are created.
+    ///
+    /// | Platform | Field                        | Value                                  |
+    /// |----------|------------------------------|----------------------------------------|
+    /// | Android  | `android.ttl`                | `ttl` in the protobuf format, `"60s"`  |
+    /// | APNs     | `apns-expiration` header     | current UNIX time plus `ttl` seconds   |
+    /// | Web push | `TTL` header                 | `ttl` seconds, `"60"`                  |
+    ///
+    /// APNs and web push values are whole seconds, fractions are dropped.
+    /// Because APNs uses an absolute time, call this just before sending.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.set_ttl_with_clock(&SystemClock, ttl)
+    }
+
+    /// Same as [Message::set_ttl] but the APNs expiration is `ttl` from
+    /// the current time of `clock`.
+    pub fn set_ttl_with_clock(&mut self, clock: &dyn Clock, ttl: Duration) {
+        self.set_ttl_at(clock.now(), ttl)
+    }
+
+    /// Same as [Message::set_ttl] but the APNs expiration is `ttl` from
+    /// `now` instead of from the current time.
+    pub fn set_ttl_at(&mut self, now: DateTime<Utc>, ttl: Duration) {
+        self.android
+            .get_or_insert_with(AndroidConfig::default)
+            .set_ttl_duration(ttl);
+
+        self.apns
+            .get_or_insert_with(ApnsConfig::default)
+            .set_header(APNS_EXPIRATION_HEADER, apns_expiration(now, ttl));
+
+        self.webpush
+            .get_or_insert_with(WebpushConfig::default)
+            .set_header(WEBPUSH_TTL_HEADER, ttl.as_secs().to_string());
+    }
+}
+
+/// `apns-expiration` header value for a message sent at `now` with `ttl`.
+pub(super) fn apns_expiration(now: DateTime<Utc>, ttl: Duration) -> String {
+    now.timestamp().saturating_add(ttl.as_secs() as i64).to_string()
+}
@@ -0,0 +1,84 @@
+use serde_json::Value;
+
+use super::target::{condition_topic_count, MAX_CONDITION_TOPICS};
+use super::{AndroidNotificationError, ApnsError, Message, Target, TargetError};
+
+/// Data keys which FCM reserves.
+/// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages#resource:-message>
+const RESERVED_DATA_KEYS: [&str; 2] = ["from", "message_type"];
+
+/// Data key prefixes which FCM reserves.
+const RESERVED_DATA_KEY_PREFIXES: [&str; 2] = ["google", "gcm"];
+
+/// Error cases of [Message::validate].
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum MessageError {
+    #[error("Message has no data, notification or platform specific config")]
+    Empty,
+    #[error("Data key is reserved by FCM: {0:?}")]
+    ReservedDataKey(String),
+    #[error("Invalid Android notification: {0}")]
+    AndroidNotification(#[from] AndroidNotificationError),
+    #[error("Invalid APNs config: {0}")]
+    Apns(#[from] ApnsError),
+    #[error("Invalid target: {0}")]
+    Target(#[from] TargetError),
+}
+
+impl Message {
+    /// Check that FCM accepts the message, so that mistakes are found
+    /// without sending the message.
+    ///
+    /// Keys of `data` and `android.data` must not be reserved words
+    /// (`from`, `message_type` or a word starting with `google` or `gcm`).
+    /// Encoding of keys and values is not checked, because strings of
+    /// [serde_json::Value] are always valid UTF-8 (`serde_json` rejects
+    /// lone surrogate escapes when parsing).
+    ///
+    /// Message must have at least one of `data`, `notification`,
+    /// `android`, `webpush` or `apns`. Android notification is checked
+    /// with [crate::message::AndroidNotification::validate] and APNs
+    /// config with [crate::message::ApnsConfig::validate].
+    ///
+    /// Condition target must have at most [MAX_CONDITION_TOPICS] topics.
+    pub fn validate(&self) -> Result<(), MessageError> {
+        let is_empty = self.data.is_none()
+            && self.notification.is_none()
+            && self.android.is_none()
+            && self.webpush.is_none()
+            && self.apns.is_none();
+        if is_empty {
+            return Err(MessageError::Empty);
+        }
+        if let Target::Condition(condition) = &self.target {
+            let topic_count = condition_topic_count(condition);
+            if topic_count > MAX_CONDITION_TOPICS {
+                return Err(TargetError::TooManyTopics(topic_count).into());
+            }
+        }
+        validate_data(self.data.as_ref())?;
+        validate_data(self.android.as_ref().and_then(|android| android.data.as_ref()))?;
+        if let Some(notification) = self.android.as_ref().and_then(|android| android.notification.as_ref()) {
+            notification.validate()?;
+        }
+        if let Some(apns) = &self.apns {
+            apns.validate()?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_data(data: Option<&Value>) -> Result<(), MessageError> {
+    let data = match data {
+        Some(Value::Object(data)) => data,
+        _ => return Ok(()),
+    };
+    for key in data.keys() {
+        let is_reserved = RESERVED_DATA_KEYS.contains(&key.as_str())
+            || RESERVED_DATA_KEY_PREFIXES.iter().any(|prefix| key.starts_with(prefix));
+        if is_reserved {
+            return Err(MessageError::ReservedDataKey(key.clone()));
+        }
+    }
+    Ok(())
+}
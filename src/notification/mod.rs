@@ -1,9 +1,10 @@
 #[cfg(test)]
 mod tests;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#notification>
 pub struct Notification {
     /// The notification's title.
@@ -18,3 +19,60 @@ pub struct Notification {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image: Option<String>,
 }
+
+/// Title length in characters after which Android devices commonly
+/// truncate the title. The limit is approximate and varies by device.
+pub const DEFAULT_MAX_TITLE_CHARS: usize = 65;
+
+/// Body length in characters after which Android and iOS devices
+/// commonly truncate the body of a collapsed notification. The limit is
+/// approximate and varies by device.
+pub const DEFAULT_MAX_BODY_CHARS: usize = 240;
+
+/// Error cases of [Notification::validate_lengths].
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum NotificationError {
+    #[error("Title has {length} characters but maximum is {max}")]
+    TitleTooLong { length: usize, max: usize },
+    #[error("Body has {length} characters but maximum is {max}")]
+    BodyTooLong { length: usize, max: usize },
+}
+
+impl Notification {
+    /// Check that title and body have at most `max_title` and `max_body`
+    /// characters (not bytes). FCM does not limit the lengths, but devices
+    /// truncate long texts. See [DEFAULT_MAX_TITLE_CHARS] and
+    /// [DEFAULT_MAX_BODY_CHARS].
+    pub fn validate_lengths(&self, max_title: usize, max_body: usize) -> Result<(), NotificationError> {
+        if let Some(length) = self.title.as_ref().map(|title| title.chars().count()) {
+            if length > max_title {
+                return Err(NotificationError::TitleTooLong { length, max: max_title });
+            }
+        }
+        if let Some(length) = self.body.as_ref().map(|body| body.chars().count()) {
+            if length > max_body {
+                return Err(NotificationError::BodyTooLong { length, max: max_body });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&Notification> for Value {
+    /// Web Notification options for [crate::message::WebpushConfig]
+    /// `notification` with `title`, `body` and `image`.
+    fn from(notification: &Notification) -> Self {
+        let mut options = Map::new();
+        let fields = [
+            ("title", &notification.title),
+            ("body", &notification.body),
+            ("image", &notification.image),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                options.insert(key.to_string(), Value::String(value.clone()));
+            }
+        }
+        Value::Object(options)
+    }
+}
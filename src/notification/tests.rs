@@ -1,5 +1,5 @@
-use crate::message::Notification;
-use serde_json::json;
+use crate::message::{AndroidNotification, Notification, NotificationError};
+use serde_json::{json, Value};
 
 #[test]
 fn should_be_able_to_render_a_full_notification_to_json() {
@@ -20,3 +20,67 @@ fn should_be_able_to_render_a_full_notification_to_json() {
 
     assert_eq!(expected_payload, payload);
 }
+
+#[test]
+fn should_convert_notification_to_android_notification() {
+    let not = Notification {
+        title: Some("foo".to_string()),
+        body: Some("bar".to_string()),
+        image: Some("https://my.image.com/test.jpg".to_string()),
+    };
+
+    let android = AndroidNotification::from(&not);
+
+    assert_eq!(not.title, android.title);
+    assert_eq!(not.body, android.body);
+    assert_eq!(not.image, android.image);
+    assert_eq!(None, android.icon);
+}
+
+#[test]
+fn should_convert_notification_to_webpush_notification() {
+    let not = Notification {
+        title: Some("foo".to_string()),
+        body: None,
+        image: Some("https://my.image.com/test.jpg".to_string()),
+    };
+
+    let webpush = Value::from(&not);
+
+    let expected = json!({
+        "title": "foo",
+        "image": "https://my.image.com/test.jpg",
+    });
+
+    assert_eq!(expected, webpush);
+}
+
+#[test]
+fn should_accept_notification_within_length_limits() {
+    let notification = Notification {
+        title: Some("äöå".to_string()),
+        body: Some("body".to_string()),
+        image: None,
+    };
+
+    assert_eq!(Ok(()), notification.validate_lengths(3, 4));
+    assert_eq!(Ok(()), Notification::default().validate_lengths(0, 0));
+}
+
+#[test]
+fn should_reject_notification_over_length_limits() {
+    let notification = Notification {
+        title: Some("äöå".to_string()),
+        body: Some("body".to_string()),
+        image: None,
+    };
+
+    assert_eq!(
+        Err(NotificationError::TitleTooLong { length: 3, max: 2 }),
+        notification.validate_lengths(2, 4)
+    );
+    assert_eq!(
+        Err(NotificationError::BodyTooLong { length: 4, max: 3 }),
+        notification.validate_lengths(3, 3)
+    );
+}
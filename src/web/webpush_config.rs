@@ -1,9 +1,9 @@
-use serde::Serialize;
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use super::webpush_fcm_options::WebpushFcmOptions;
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#webpushconfig>
 pub struct WebpushConfig {
     /// HTTP headers defined in webpush protocol.
@@ -20,6 +20,55 @@ pub struct WebpushConfig {
     pub notification: Option<Value>,
 
     /// Options for features provided by the FCM SDK for Web.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "fcmOptions")]
     pub fcm_options: Option<WebpushFcmOptions>,
 }
+
+impl WebpushConfig {
+    /// Set `headers`, replacing all previously set headers.
+    pub fn with_headers(mut self, headers: Value) -> Self {
+        self.headers = Some(headers);
+        self
+    }
+
+    /// Set web push request header `name`, see [WebpushConfig::set_header].
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_header(name, value);
+        self
+    }
+
+    /// Set `data`.
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Set `notification`.
+    pub fn with_notification(mut self, notification: Value) -> Self {
+        self.notification = Some(notification);
+        self
+    }
+
+    /// Set `fcm_options`.
+    pub fn with_fcm_options(mut self, fcm_options: WebpushFcmOptions) -> Self {
+        self.fcm_options = Some(fcm_options);
+        self
+    }
+
+    /// Get string value of web push request header `name`.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.as_ref()?.get(name)?.as_str()
+    }
+
+    /// Set web push request header `name`. If `headers` is not a JSON
+    /// object it is replaced with an object.
+    pub fn set_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let headers = self.headers.get_or_insert_with(|| Value::Object(Map::new()));
+        if !headers.is_object() {
+            *headers = Value::Object(Map::new());
+        }
+        if let Value::Object(headers) = headers {
+            headers.insert(name.into(), Value::String(value.into()));
+        }
+    }
+}
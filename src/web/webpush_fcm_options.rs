@@ -1,11 +1,14 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Serialize)]
+use crate::message::AnalyticsLabel;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// <https://firebase.google.com/docs/reference/fcm/rest/v1/projects.messages?authuser=0#webpushfcmoptions>
 pub struct WebpushFcmOptions {
     /// The link to open when the user clicks on the notification.
     pub link: String,
 
     /// Label associated with the message's analytics data.
-    pub analytics_label: String,
+    #[serde(alias = "analyticsLabel")]
+    pub analytics_label: AnalyticsLabel,
 }